@@ -3,9 +3,9 @@ use anyhow::Result;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
 use futures::stream::StreamExt;
 use futures::FutureExt;
-use genai_types::MessageContent;
+use genai_types::{CompletionResponse, MessageContent};
 use ratatui::{backend::Backend, Terminal};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::SocketAddr;
 use std::time::{Duration, Instant};
 use theater::messages::ChannelParticipant;
@@ -15,8 +15,50 @@ use theater_server::{ManagementCommand, ManagementResponse};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::chat::{ChatManager, ChatMessage, ChatStateResponse};
+use crate::chat::{ChatEntry, ChatManager, ChatMessage, ChatStateResponse, ErrorInfo};
 use crate::config::{CompatibleArgs, LoadingState, LoadingStep, StepStatus};
+use crate::config_manager::{ConversationConfig, ModelPricing};
+use crate::session_manager::{ApprovalDecision, SessionData, SessionManager, SessionUsage};
+use crate::tool_calls::{
+    count_tool_calls, mark_blocked_calls, pair_tool_calls, ToolCall, ToolTimings,
+};
+use crate::tool_inventory::ToolInventory;
+use crate::tool_policy::ToolPolicy;
+use crate::tool_renderers::RendererRegistry;
+use crate::ui::ToolCardCache;
+
+/// Prompt sent to the model when compacting a conversation
+const COMPACTION_PROMPT: &str = "Summarize our conversation so far so it can be continued in a fresh session. \
+Include the goals, the decisions we made, the current state of any work in progress (files, commands, \
+open questions) and the next steps. Reply with the summary only.";
+
+/// Keys pressed this soon after the approval modal shows a tool call are ignored, so
+/// typing that was already under way does not decide it
+const APPROVAL_KEY_DELAY: Duration = Duration::from_millis(500);
+
+/// Commands typed into the input area that are handled by th-chat instead of the model
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashCommand {
+    /// Summarize the conversation and continue in a new session seeded with the summary
+    Compact,
+    /// Show the MCP servers and tools the actor loaded
+    Tools,
+    /// Show every tool call in the conversation, optionally only those of one tool
+    Timeline(Option<String>),
+}
+
+impl SlashCommand {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim() {
+            "/compact" => Some(SlashCommand::Compact),
+            "/tools" => Some(SlashCommand::Tools),
+            "/timeline" => Some(SlashCommand::Timeline(None)),
+            input => input
+                .strip_prefix("/timeline ")
+                .map(|tool| SlashCommand::Timeline(Some(tool.trim().to_string()))),
+        }
+    }
+}
 
 
 /// Current application mode
@@ -50,6 +92,50 @@ impl AppMode {
     }
 }
 
+/// Token usage reported for a single completion
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TurnUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TurnUsage {
+    pub fn from_completion(completion: &CompletionResponse) -> Self {
+        Self {
+            input_tokens: completion.usage.input_tokens as u64,
+            output_tokens: completion.usage.output_tokens as u64,
+        }
+    }
+}
+
+/// A failed generation waiting to be retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRetry {
+    /// When the retry will be sent
+    pub at: Instant,
+    /// Number of the upcoming attempt
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Error code that triggered the retry
+    pub code: String,
+}
+
+impl PendingRetry {
+    /// Whole seconds until the retry is sent (rounded up)
+    pub fn seconds_remaining(&self) -> u64 {
+        let remaining = self.at.saturating_duration_since(Instant::now());
+        (remaining.as_millis() as u64).div_ceil(1000)
+    }
+}
+
+/// A tool call the actor is holding until the user approves or denies it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingApproval {
+    pub tool_use_id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+}
+
 /// Application state
 #[derive(Debug)]
 pub struct App {
@@ -114,6 +200,50 @@ pub struct App {
     pub tool_display_mode: crate::config::ToolDisplayMode,
     /// Whether to show the split screen (help panel)
     pub show_split_screen: bool,
+    /// Token usage of the most recent completion
+    pub last_turn_usage: Option<TurnUsage>,
+    /// Cumulative token usage for the current session
+    pub session_usage: SessionUsage,
+    /// Whether we're waiting for the model to summarize the conversation
+    pub compaction_pending: bool,
+    /// Summary received from the model, waiting to seed the new session
+    pending_compaction_summary: Option<String>,
+    /// Set when the user asks to retry the generation that failed
+    retry_requested: bool,
+    /// Automatic retry scheduled after a transient error
+    pub pending_retry: Option<PendingRetry>,
+    /// MCP servers and tools as last reported by the actor
+    pub tool_inventory: Option<ToolInventory>,
+    /// Show the tool inventory popup
+    pub show_tools: bool,
+    /// Scroll offset of the tool inventory popup
+    pub tools_scroll: usize,
+    /// Allowed/denied tool globs, used to mark blocked tools in `/tools`
+    pub tool_policy: ToolPolicy,
+    /// Renderers for tool inputs and outputs, with the config's tool bindings
+    pub tool_renderers: RendererRegistry,
+    /// Start times and durations of tool calls seen live
+    pub tool_timings: ToolTimings,
+    /// Tool call cards showing only their header, by tool use ID
+    pub collapsed_tool_calls: HashSet<String>,
+    /// Tool calls paired with their results, rebuilt when the messages change
+    pub paired_tool_calls: Vec<ToolCall>,
+    /// Rendered tool card bodies, kept between redraws
+    pub tool_cards: ToolCardCache,
+    /// Show the tool call timeline popup
+    pub show_timeline: bool,
+    /// Only list calls of this tool in the timeline
+    pub timeline_filter: Option<String>,
+    /// Selected row of the timeline
+    pub timeline_selected: usize,
+    /// Tool calls waiting for approval; the first one is shown in the approval modal
+    pub pending_approvals: VecDeque<PendingApproval>,
+    /// When the approval modal started showing its current tool call
+    approval_shown_at: Option<Instant>,
+    /// Decisions made but not yet sent to the actor
+    approval_decisions: Vec<(PendingApproval, ApprovalDecision)>,
+    /// Tools approved automatically for the rest of the session
+    always_allowed_tools: HashSet<String>,
 }
 
 impl Default for App {
@@ -151,6 +281,28 @@ impl Default for App {
             cursor_col: 0,
             tool_display_mode: crate::config::ToolDisplayMode::default(),
             show_split_screen: true, // Default to showing the split screen
+            last_turn_usage: None,
+            session_usage: SessionUsage::default(),
+            compaction_pending: false,
+            pending_compaction_summary: None,
+            retry_requested: false,
+            pending_retry: None,
+            tool_inventory: None,
+            show_tools: false,
+            tools_scroll: 0,
+            tool_policy: ToolPolicy::default(),
+            tool_renderers: RendererRegistry::default(),
+            tool_timings: ToolTimings::default(),
+            collapsed_tool_calls: HashSet::new(),
+            paired_tool_calls: Vec::new(),
+            tool_cards: ToolCardCache::default(),
+            show_timeline: false,
+            timeline_filter: None,
+            timeline_selected: 0,
+            pending_approvals: VecDeque::new(),
+            approval_shown_at: None,
+            approval_decisions: Vec::new(),
+            always_allowed_tools: HashSet::new(),
         }
     }
 }
@@ -213,6 +365,24 @@ impl App {
             return Ok(None);
         }
 
+        // A tool call waiting for approval takes every key until it is decided. Only
+        // y, n and A decide it, and not until the modal has been up for a moment.
+        if !self.pending_approvals.is_empty() {
+            let settled = self
+                .approval_shown_at
+                .map_or(true, |shown| shown.elapsed() >= APPROVAL_KEY_DELAY);
+            let decision = match key_event.code {
+                KeyCode::Char('y') => Some(ApprovalDecision::Approved),
+                KeyCode::Char('n') => Some(ApprovalDecision::Denied),
+                KeyCode::Char('A') => Some(ApprovalDecision::AlwaysAllowed),
+                _ => None,
+            };
+            if let Some(decision) = decision.filter(|_| settled) {
+                self.decide_pending_approval(decision);
+            }
+            return Ok(None);
+        }
+
         // Handle help popup
         if self.show_help {
             match key_event.code {
@@ -224,6 +394,47 @@ impl App {
             return Ok(None);
         }
 
+        // Handle tool inventory popup
+        if self.show_tools {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.show_tools = false;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.tools_scroll = self.tools_scroll.saturating_add(1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.tools_scroll = self.tools_scroll.saturating_sub(1);
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+
+        // Handle tool call timeline popup
+        if self.show_timeline {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.show_timeline = false;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    let last = self.timeline_calls().len().saturating_sub(1);
+                    self.timeline_selected = (self.timeline_selected + 1).min(last);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.timeline_selected = self.timeline_selected.saturating_sub(1);
+                }
+                KeyCode::Char('f') | KeyCode::Tab => {
+                    self.cycle_timeline_filter();
+                }
+                KeyCode::Enter => {
+                    self.open_selected_tool_call();
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+
         match self.app_mode {
             AppMode::View => match key_event.code {
                 KeyCode::Char('q') => {
@@ -338,6 +549,14 @@ impl App {
                     // Toggle collapse/expand for selected message
                     self.toggle_message_collapse();
                 }
+                KeyCode::Char('o') => {
+                    // Collapse or expand the tool call cards of the selected message
+                    self.toggle_tool_cards();
+                }
+                KeyCode::Char('r') => {
+                    // Retry the generation that produced the selected error
+                    self.retry_selected_error();
+                }
                 KeyCode::Char('t') => {
                     // Cycle tool display mode
                     self.cycle_tool_display_mode();
@@ -362,6 +581,10 @@ impl App {
         match payload {
             ChatStateResponse::ChatMessage { message } => {
                 info!("Received chat message: {:?}", message);
+                if message.is_completion() {
+                    self.waiting_for_response = false;
+                }
+                self.tool_timings.observe(&message.as_message());
                 self.add_message_to_chain(message);
             }
             ChatStateResponse::Head { head } => {
@@ -370,6 +593,30 @@ impl App {
             }
             ChatStateResponse::Error { error } => {
                 error!("Error from server: {:?}", error);
+                self.waiting_for_response = false;
+                self.compaction_pending = false;
+                self.add_error_entry(error);
+            }
+            ChatStateResponse::ToolApprovalRequest {
+                tool_use_id,
+                name,
+                input,
+            } => {
+                info!("Tool call {} ({}) needs approval", tool_use_id, name);
+                let approval = PendingApproval {
+                    tool_use_id,
+                    name,
+                    input,
+                };
+                if self.always_allowed_tools.contains(&approval.name) {
+                    self.approval_decisions
+                        .push((approval, ApprovalDecision::AutoApproved));
+                } else {
+                    if self.pending_approvals.is_empty() {
+                        self.approval_shown_at = Some(Instant::now());
+                    }
+                    self.pending_approvals.push_back(approval);
+                }
             }
             _ => {
                 error!("Unknown message type from server");
@@ -589,6 +836,10 @@ impl App {
             }
         };
 
+        if let Some(completion) = message.as_completion() {
+            self.last_turn_usage = Some(TurnUsage::from_completion(completion));
+        }
+
         // Create a new message with the guaranteed ID
         let mut message_with_id = message.clone();
         message_with_id.id = Some(message_id.clone());
@@ -609,6 +860,97 @@ impl App {
         self.auto_scroll_to_bottom();
     }
 
+    /// Show an error as an entry in the chat view
+    pub fn add_error_entry(&mut self, error: ErrorInfo) {
+        self.add_message_to_chain(ChatMessage {
+            id: Some(format!("error-{}", Uuid::new_v4())),
+            parent_id: self.client_head.clone(),
+            entry: ChatEntry::Error(error),
+        });
+    }
+
+    /// Remove a message from the chain by ID
+    fn remove_message_from_chain(&mut self, message_id: &str) {
+        let removed = self
+            .messages
+            .iter()
+            .position(|message| message.id.as_deref() == Some(message_id));
+        self.messages_by_id.remove(message_id);
+        self.message_chain.retain(|id| id != message_id);
+        self.rebuild_messages_vector();
+
+        // Collapsed state is kept by index, so shift the entries after the removed message
+        if let Some(removed) = removed {
+            self.collapsed_messages = self
+                .collapsed_messages
+                .iter()
+                .filter(|&&index| index != removed)
+                .map(|&index| if index > removed { index - 1 } else { index })
+                .collect();
+        }
+    }
+
+    /// Request a retry if the selected message is an error entry, dropping the entry
+    pub fn retry_selected_error(&mut self) {
+        let Some(index) = self.selected_message_index else {
+            return;
+        };
+        let Some(error_id) = self
+            .messages
+            .get(index)
+            .filter(|message| message.as_error().is_some())
+            .and_then(|message| message.id.clone())
+        else {
+            return;
+        };
+
+        info!("Retrying generation after error entry {}", error_id);
+        self.remove_message_from_chain(&error_id);
+        self.selected_message_index = if self.messages.is_empty() {
+            None
+        } else {
+            Some(index.min(self.messages.len() - 1))
+        };
+        self.retry_requested = true;
+    }
+
+    /// Take a pending retry request
+    pub fn take_retry_request(&mut self) -> bool {
+        std::mem::take(&mut self.retry_requested)
+    }
+
+    /// Decide the tool call shown in the approval modal
+    pub fn decide_pending_approval(&mut self, decision: ApprovalDecision) {
+        let Some(approval) = self.pending_approvals.pop_front() else {
+            return;
+        };
+        let name = approval.name.clone();
+        self.approval_decisions.push((approval, decision));
+
+        if decision == ApprovalDecision::AlwaysAllowed {
+            self.always_allowed_tools.insert(name.clone());
+            // Calls to the same tool already waiting are covered by the decision
+            let (covered, waiting): (VecDeque<_>, VecDeque<_>) = self
+                .pending_approvals
+                .drain(..)
+                .partition(|pending| pending.name == name);
+            self.pending_approvals = waiting;
+            self.approval_decisions.extend(
+                covered
+                    .into_iter()
+                    .map(|pending| (pending, ApprovalDecision::AutoApproved)),
+            );
+        }
+
+        // The next waiting call gets the same delay before keys decide it
+        self.approval_shown_at = (!self.pending_approvals.is_empty()).then(Instant::now);
+    }
+
+    /// Take the approval decisions that still have to be sent to the actor
+    pub fn take_approval_decisions(&mut self) -> Vec<(PendingApproval, ApprovalDecision)> {
+        std::mem::take(&mut self.approval_decisions)
+    }
+
     /// Rebuild the messages vector from the chain for UI rendering
     fn rebuild_messages_vector(&mut self) {
         self.messages = self
@@ -617,6 +959,7 @@ impl App {
             .filter_map(|id| self.messages_by_id.get(id))
             .cloned()
             .collect();
+        self.refresh_tool_calls();
     }
 
     /// Auto-scroll to bottom after adding messages (keep at bottom by default)
@@ -631,6 +974,8 @@ impl App {
         self.messages_by_id.clear();
         self.message_chain.clear();
         self.client_head = None;
+        self.last_turn_usage = None;
+        self.refresh_tool_calls();
         self.update_scroll();
     }
 
@@ -700,6 +1045,29 @@ impl App {
         Ok(())
     }
 
+    /// Estimated fraction of the context window used, based on the input tokens
+    /// reported for the last completion
+    pub fn context_usage_ratio(&self, context_window: Option<u32>) -> Option<f64> {
+        let window = context_window.filter(|w| *w > 0)?;
+        let usage = self.last_turn_usage?;
+        Some(usage.input_tokens as f64 / window as f64)
+    }
+
+    /// Add a completion's token usage to the session totals, estimating its cost when
+    /// the model has an entry in the price table
+    pub fn record_completion_usage(
+        &mut self,
+        completion: &CompletionResponse,
+        pricing: &HashMap<String, ModelPricing>,
+    ) {
+        let usage = TurnUsage::from_completion(completion);
+        let cost = pricing
+            .get(&completion.model)
+            .map(|price| price.estimate_cost(usage.input_tokens, usage.output_tokens));
+        self.session_usage
+            .record(usage.input_tokens, usage.output_tokens, cost);
+    }
+
     /// Update scroll state based on messages
     pub fn update_scroll(&mut self) {
         // The scroll state will be updated in the UI rendering
@@ -792,7 +1160,7 @@ impl App {
 
     /// Update thinking animation
     pub fn update_thinking_animation(&mut self) {
-        if self.waiting_for_response
+        if (self.waiting_for_response || self.compaction_pending)
             && self.last_thinking_update.elapsed() > Duration::from_millis(500)
         {
             self.thinking_dots = match self.thinking_dots.as_str() {
@@ -842,6 +1210,14 @@ impl App {
         }
     }
 
+    /// Complete current step with a warning message
+    pub fn warn_current_step(&mut self, warning: String) {
+        if self.current_step_index < self.loading_steps.len() {
+            self.loading_steps[self.current_step_index].status = StepStatus::Warning(warning);
+            self.current_step_index += 1;
+        }
+    }
+
     /// Fail current step with error message
     pub fn fail_current_step(&mut self, error: String) {
         if self.current_step_index < self.loading_steps.len() {
@@ -861,7 +1237,7 @@ impl App {
     pub fn is_loading_complete(&self) -> bool {
         self.loading_steps
             .iter()
-            .all(|step| matches!(step.status, StepStatus::Success))
+            .all(|step| matches!(step.status, StepStatus::Success | StepStatus::Warning(_)))
     }
 
     /// Toggle help popup
@@ -873,6 +1249,94 @@ impl App {
         self.show_split_screen = !self.show_split_screen;
     }
 
+    /// Store the actor's tool inventory for `/tools`
+    pub fn set_tool_inventory(&mut self, inventory: ToolInventory) {
+        self.tool_inventory = Some(inventory);
+        // Which calls count as blocked depends on the servers providing each tool
+        self.refresh_tool_calls();
+    }
+
+    /// Every tool call in the conversation, paired with its result
+    pub fn tool_calls(&self) -> &[ToolCall] {
+        &self.paired_tool_calls
+    }
+
+    /// Pair the tool calls in the messages again, dropping the cached cards of
+    /// calls that are gone. Called whenever the messages change, so redraws don't
+    /// have to.
+    fn refresh_tool_calls(&mut self) {
+        let messages: Vec<_> = self.messages.iter().map(ChatMessage::as_message).collect();
+        let mut calls = pair_tool_calls(&messages);
+        // A failed call counts as blocked when the config blocks its tool on a
+        // server that provides it
+        mark_blocked_calls(&mut calls, |tool| {
+            let servers = self
+                .tool_inventory
+                .iter()
+                .flat_map(|inventory| inventory.servers_with_tool(tool));
+            self.tool_policy.check_call(servers, tool)
+        });
+        self.tool_cards.retain(&calls);
+        self.paired_tool_calls = calls;
+    }
+
+    /// Tool calls listed in the timeline, with the tool filter applied
+    pub fn timeline_calls(&self) -> Vec<ToolCall> {
+        self.tool_calls()
+            .iter()
+            .filter(|call| {
+                self.timeline_filter
+                    .as_ref()
+                    .map_or(true, |tool| &call.name == tool)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Open the timeline, listing only calls of `tool` if given
+    pub fn show_tool_timeline(&mut self, tool: Option<String>) {
+        self.timeline_filter = tool;
+        self.timeline_selected = 0;
+        self.show_timeline = true;
+    }
+
+    /// Filter the timeline by the next tool used in the conversation, then by none
+    fn cycle_timeline_filter(&mut self) {
+        let mut tools: Vec<String> = self
+            .tool_calls()
+            .iter()
+            .map(|call| call.name.clone())
+            .collect();
+        tools.sort();
+        tools.dedup();
+
+        let next = match &self.timeline_filter {
+            None => 0,
+            Some(tool) => match tools.iter().position(|name| name == tool) {
+                Some(index) => index + 1,
+                None => 0,
+            },
+        };
+        self.timeline_filter = tools.get(next).cloned();
+        self.timeline_selected = 0;
+    }
+
+    /// Close the timeline and select the message holding the selected tool call
+    fn open_selected_tool_call(&mut self) {
+        let Some(call) = self
+            .timeline_calls()
+            .into_iter()
+            .nth(self.timeline_selected)
+        else {
+            return;
+        };
+        self.show_timeline = false;
+        self.app_mode = AppMode::Chat;
+        self.show_message_selection = true;
+        self.selected_message_index = Some(call.message_index);
+        self.ensure_selected_message_visible();
+    }
+
 
 
     /// Navigate to previous message (vim k)
@@ -988,6 +1452,34 @@ impl App {
         }
     }
 
+    /// Collapse the tool call cards in the selected message, or expand them all if
+    /// they are already collapsed
+    pub fn toggle_tool_cards(&mut self) {
+        let Some(message) = self
+            .selected_message_index
+            .and_then(|index| self.messages.get(index))
+        else {
+            return;
+        };
+        let ids: Vec<String> = message
+            .as_message()
+            .content
+            .into_iter()
+            .filter_map(|content| match content {
+                genai_types::MessageContent::ToolUse { id, .. } => Some(id),
+                _ => None,
+            })
+            .collect();
+
+        if ids.iter().all(|id| self.collapsed_tool_calls.contains(id)) {
+            for id in &ids {
+                self.collapsed_tool_calls.remove(id);
+            }
+        } else {
+            self.collapsed_tool_calls.extend(ids);
+        }
+    }
+
     /// Check if a message is collapsed
     pub fn is_message_collapsed(&self, index: usize) -> bool {
         self.collapsed_messages.contains(&index)
@@ -1035,34 +1527,83 @@ impl App {
         terminal: &mut Terminal<B>,
         chat_manager: &mut crate::chat::ChatManager,
         args: &CompatibleArgs,
+        config: &ConversationConfig,
         session_manager: &crate::session_manager::SessionManager,
         session_data: &mut crate::session_manager::SessionData,
     ) -> Result<()> {
         info!("Starting session-aware chat loop for '{}'", session_data.name);
         
         let server_addr: SocketAddr = args.server.parse().context("Invalid server address")?;
-        let mut connection = TheaterConnection::new(server_addr);
-        
-        // Send the initial messages to the server to listen for head and message updates
-        let message = ManagementCommand::OpenChannel {
-            actor_id: ChannelParticipant::Actor(
-                TheaterId::parse(&chat_manager.actor_id).expect("Invalid actor ID"),
-            ),
-            initial_message: vec![],
-        };
-
-        info!("Sending initial message to server: {:?}", message);
-        connection
-            .send(message)
-            .await
-            .context("Failed to send initial message to Theater server")?;
+        let mut connection = open_actor_channel(server_addr, &chat_manager.actor_id).await?;
 
         self.connection_status = format!("Connected to {} (Session: {})", server_addr, session_data.name);
 
         let mut reader = EventStream::new();
         let mut message_count = session_data.message_count;
+        self.session_usage = session_data.usage.clone();
+        self.always_allowed_tools = session_data.always_allowed_tools.iter().cloned().collect();
 
         loop {
+            // Finish a pending compaction here, outside the select, so the channel can be swapped
+            if let Some(summary) = self.pending_compaction_summary.take() {
+                match self
+                    .complete_compaction(summary, chat_manager, args, config, session_manager, session_data)
+                    .await
+                {
+                    Ok(()) => {
+                        connection = open_actor_channel(server_addr, &chat_manager.actor_id).await?;
+                        message_count = 0;
+                        self.connection_status =
+                            format!("Connected to {} (Session: {})", server_addr, session_data.name);
+                    }
+                    Err(e) => {
+                        error!("Failed to compact conversation: {:?}", e);
+                        self.add_error_entry(ErrorInfo {
+                            code: "compaction_failed".to_string(),
+                            message: format!("Could not compact the conversation: {:#}", e),
+                            details: None,
+                        });
+                    }
+                }
+            }
+
+            // Answer tool calls the actor is holding for approval. Each decision is
+            // logged before it is sent, and a failed send is reported without ending
+            // the session, so the remaining decisions still go out.
+            let decisions = self.take_approval_decisions();
+            if !decisions.is_empty() {
+                for (approval, decision) in decisions {
+                    let approved = decision.is_approved();
+                    let tool_use_id = approval.tool_use_id.clone();
+                    let name = approval.name.clone();
+                    session_data.record_tool_decision(
+                        approval.tool_use_id,
+                        approval.name,
+                        approval.input,
+                        decision,
+                    );
+                    if let Err(e) = chat_manager
+                        .send_tool_decision(&tool_use_id, approved)
+                        .await
+                    {
+                        error!("Failed to send tool decision for {}: {:?}", tool_use_id, e);
+                        self.add_error_entry(ErrorInfo {
+                            code: "tool_decision_failed".to_string(),
+                            message: format!(
+                                "Could not send the {} decision for {} to the actor: {:#}",
+                                if approved { "approve" } else { "deny" },
+                                name,
+                                e
+                            ),
+                            details: None,
+                        });
+                    }
+                }
+                if let Err(e) = session_manager.save_session(session_data) {
+                    warn!("Failed to save tool decisions: {}", e);
+                }
+            }
+
             // Update animations
             self.update_thinking_animation();
             if self.is_loading {
@@ -1086,13 +1627,53 @@ impl App {
                             if let Ok(payload) = serde_json::from_slice::<crate::chat::ChatStateResponse>(&message) {
                                 // Check if it's a ChatMessage before processing
                                 let is_chat_message = matches!(payload, crate::chat::ChatStateResponse::ChatMessage { .. });
+
+                                // Track token usage for completions as they arrive
+                                if let crate::chat::ChatStateResponse::ChatMessage { message } = &payload {
+                                    if let Some(completion) = message.as_completion() {
+                                        self.record_completion_usage(completion, &args.model_pricing);
+                                        session_data.usage = self.session_usage.clone();
+                                        chat_manager.reset_retries();
+
+                                        // The first completion with text after /compact is the summary
+                                        if self.compaction_pending {
+                                            let summary = completion_text(message);
+                                            if !summary.trim().is_empty() {
+                                                self.compaction_pending = false;
+                                                self.pending_compaction_summary = Some(summary);
+                                            }
+                                        }
+                                    }
+                                }
                                 
-                                let _ = self.process_channel_message(payload);
+                                // Transient provider errors are retried instead of shown
+                                let retry = match &payload {
+                                    crate::chat::ChatStateResponse::Error { error } => chat_manager
+                                        .schedule_retry(error)
+                                        .map(|(delay, attempt)| PendingRetry {
+                                            at: Instant::now() + delay,
+                                            attempt,
+                                            max_attempts: chat_manager.retry_policy().max_attempts,
+                                            code: error.code.clone(),
+                                        }),
+                                    _ => None,
+                                };
+
+                                if let Some(retry) = retry {
+                                    warn!(
+                                        "Generation failed with {}, retrying (attempt {}/{})",
+                                        retry.code, retry.attempt, retry.max_attempts
+                                    );
+                                    self.pending_retry = Some(retry);
+                                } else {
+                                    let _ = self.process_channel_message(payload);
+                                }
                                 
                                 // Update session metadata when we get new messages
                                 if is_chat_message {
                                     message_count += 1;
                                     session_data.message_count = message_count;
+                                    session_data.tool_calls = count_tool_calls(self.tool_calls());
                                     session_data.update_access_time();
                                     
                                     // Periodically save session state
@@ -1129,11 +1710,45 @@ impl App {
                         Some(Ok(event)) => {
                             if let Event::Key(key_event) = event {
                                 if let Some(message) = self.handle_key_event(key_event)? {
-                                    chat_manager.send_message(message.clone()).await?;
+                                    match SlashCommand::parse(&message) {
+                                        Some(SlashCommand::Compact) => {
+                                            if self.compaction_pending || self.messages.is_empty() {
+                                                warn!("Ignoring /compact: nothing to compact or already compacting");
+                                            } else {
+                                                info!("Requesting summary to compact session '{}'", session_data.name);
+                                                chat_manager.send_message(COMPACTION_PROMPT.to_string()).await?;
+                                                chat_manager.request_generation().await?;
+                                                self.compaction_pending = true;
+                                                self.waiting_for_response = true;
+                                            }
+                                        }
+                                        Some(SlashCommand::Tools) => {
+                                            // Ask again so servers that loaded late show up; an actor
+                                            // that timed out before is not asked, so this never blocks
+                                            self.set_tool_inventory(chat_manager.tool_inventory().await);
+                                            self.tools_scroll = 0;
+                                            self.show_tools = true;
+                                        }
+                                        Some(SlashCommand::Timeline(tool)) => {
+                                            self.show_tool_timeline(tool);
+                                        }
+                                        None => {
+                                            self.pending_retry = None;
+                                            chat_manager.send_message(message.clone()).await?;
+                                            chat_manager.request_generation().await?;
+                                            self.waiting_for_response = true;
+
+                                            // Update session metadata for sent messages
+                                            session_data.update_access_time();
+                                        }
+                                    }
+                                }
+
+                                if self.take_retry_request() {
+                                    self.pending_retry = None;
+                                    chat_manager.reset_retries();
                                     chat_manager.request_generation().await?;
-                                    
-                                    // Update session metadata for sent messages
-                                    session_data.update_access_time();
+                                    self.waiting_for_response = true;
                                 }
                             }
                         }
@@ -1147,6 +1762,16 @@ impl App {
                         }
                     }
                 }
+
+                // Tick while a retry is pending so the countdown stays current
+                _ = tokio::time::sleep(Duration::from_millis(250)), if self.pending_retry.is_some() => {
+                    if self.pending_retry.as_ref().map_or(false, |retry| Instant::now() >= retry.at) {
+                        if let Some(retry) = self.pending_retry.take() {
+                            info!("Retrying generation (attempt {}/{})", retry.attempt, retry.max_attempts);
+                            chat_manager.request_generation().await?;
+                        }
+                    }
+                }
             }
         }
 
@@ -1159,6 +1784,77 @@ impl App {
         Ok(())
     }
 
+    /// Move the conversation to a new session seeded with the summary of the current one
+    async fn complete_compaction(
+        &mut self,
+        summary: String,
+        chat_manager: &mut ChatManager,
+        args: &CompatibleArgs,
+        config: &ConversationConfig,
+        session_manager: &SessionManager,
+        session_data: &mut SessionData,
+    ) -> Result<()> {
+        let new_name = session_manager.next_compacted_session_name(&session_data.name);
+        info!(
+            "Compacting session '{}' into new session '{}'",
+            session_data.name, new_name
+        );
+
+        let mut new_manager = ChatManager::start_new_conversation(args, config).await?;
+
+        // Seed the new conversation and persist both sessions, stopping the new actor
+        // if any of it fails so it is not left running
+        let seeded = async {
+            let (conversation_id, store_id) = new_manager.get_metadata().await?;
+
+            let mut new_session = SessionData::new(new_name.clone(), conversation_id, store_id)
+                .with_description(format!("Compacted from '{}'", session_data.name));
+            new_session.config_preset = session_data.config_preset.clone();
+            new_session.compacted_from = Some(session_data.name.clone());
+
+            new_manager
+                .send_message(format!(
+                    "Summary of our previous conversation (session '{}'):\n\n{}",
+                    session_data.name, summary
+                ))
+                .await?;
+
+            // Link the original session to its continuation
+            let mut original = session_data.clone();
+            original.compacted_into = Some(new_name.clone());
+            original.update_access_time();
+            session_manager.save_session(&new_session)?;
+            session_manager.save_session(&original)?;
+            Ok::<_, anyhow::Error>(new_session)
+        }
+        .await;
+        let new_session = match seeded {
+            Ok(new_session) => new_session,
+            Err(e) => {
+                if let Err(stop_error) = new_manager.cleanup().await {
+                    warn!(
+                        "Failed to stop actor started for compaction: {}",
+                        stop_error
+                    );
+                }
+                return Err(e);
+            }
+        };
+
+        // Stop the old actor and switch over to the new conversation
+        if let Err(e) = chat_manager.cleanup().await {
+            warn!("Failed to stop actor of compacted session: {}", e);
+        }
+        *chat_manager = new_manager;
+        *session_data = new_session;
+
+        self.clear_conversation();
+        self.collapsed_messages.clear();
+        self.selected_message_index = None;
+        self.session_usage = SessionUsage::default();
+        Ok(())
+    }
+
     /// Cycle tool display mode
     pub fn cycle_tool_display_mode(&mut self) {
         self.tool_display_mode = self.tool_display_mode.cycle();
@@ -1188,4 +1884,38 @@ impl App {
             info!("Auto-collapsed {} tool-heavy messages", collapsed_count);
         }
     }
-}
\ No newline at end of file
+}
+
+/// Open a channel to the actor so we receive head and message updates
+async fn open_actor_channel(server_addr: SocketAddr, actor_id: &str) -> Result<TheaterConnection> {
+    let mut connection = TheaterConnection::new(server_addr);
+
+    let message = ManagementCommand::OpenChannel {
+        actor_id: ChannelParticipant::Actor(
+            actor_id.parse::<TheaterId>().context("Invalid actor ID")?,
+        ),
+        initial_message: vec![],
+    };
+
+    info!("Sending initial message to server: {:?}", message);
+    connection
+        .send(message)
+        .await
+        .context("Failed to send initial message to Theater server")?;
+
+    Ok(connection)
+}
+
+/// Concatenate the text content of a message
+fn completion_text(message: &ChatMessage) -> String {
+    message
+        .as_message()
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -14,6 +14,7 @@ async fn main() -> Result<()> {
     let config_options = ConfigLoadOptions {
         config_file: Some(PathBuf::from("test-dir/test-config.json")),
         preset: None,
+        ..Default::default()
     };
 
     match config_manager.load_config(&config_options) {
@@ -53,7 +54,10 @@ async fn main() -> Result<()> {
 
             // Test config creation
             match config_manager.create_default_config(&directory) {
-                Ok(()) => println!("✅ Successfully created default configuration"),
+                Ok(presets) => println!(
+                    "✅ Successfully created default configuration ({} example presets)",
+                    presets.len()
+                ),
                 Err(e) => println!("❌ Failed to create default configuration: {}", e),
             }
 
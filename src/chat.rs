@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use theater_client::TheaterConnection;
 use theater_server::{ManagementCommand, ManagementResponse};
@@ -11,7 +12,13 @@ use theater::id::TheaterId;
 use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
-use crate::config::{CompatibleArgs, CHAT_STATE_ACTOR_MANIFEST};
+use crate::config::CompatibleArgs;
+use crate::config_manager::{McpServer, RetryPolicy};
+use crate::tool_inventory::ToolInventory;
+
+/// How long to wait for the actor to answer `get_settings`. Chat-state actors that
+/// do not implement it never answer, so the request gives up instead of hanging.
+const SETTINGS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// Chat message structure matching the chat-state actor
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +33,8 @@ pub struct ChatMessage {
 pub enum ChatEntry {
     Message(Message),
     Completion(CompletionResponse),
+    /// Error reported by the actor (local only, never sent by chat-state)
+    Error(ErrorInfo),
 }
 
 /// Helper methods for ChatMessage
@@ -35,6 +44,12 @@ impl ChatMessage {
         match &self.entry {
             ChatEntry::Message(msg) => msg.clone(),
             ChatEntry::Completion(completion) => completion.clone().into(),
+            ChatEntry::Error(error) => Message {
+                role: Role::System,
+                content: vec![MessageContent::Text {
+                    text: error.to_string(),
+                }],
+            },
         }
     }
 
@@ -51,7 +66,13 @@ impl ChatMessage {
         }
     }
 
-
+    /// Get the error if this is an error entry
+    pub fn as_error(&self) -> Option<&ErrorInfo> {
+        match &self.entry {
+            ChatEntry::Error(error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 /// Response types from the chat-state actor
@@ -71,15 +92,44 @@ pub enum ChatStateResponse {
         conversation_id: String,
         store_id: String,
     },
+    #[serde(rename = "settings")]
+    Settings { settings: ActorSettings },
+    /// The actor is holding a tool call that matches `confirm_tools` until it is
+    /// approved or denied
+    #[serde(rename = "tool_approval_request")]
+    ToolApprovalRequest {
+        tool_use_id: String,
+        name: String,
+        #[serde(default)]
+        input: serde_json::Value,
+    },
     #[serde(rename = "success")]
     Success,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The part of the actor's settings th-chat reads back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActorSettings {
+    /// MCP servers as the actor loaded them, with the tools each reported
+    #[serde(default)]
+    pub mcp_servers: Vec<McpServer>,
+    /// Globs of tool calls the actor holds for approval. Actors without tool
+    /// approval leave it out.
+    #[serde(default)]
+    pub confirm_tools: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorInfo {
-    code: String,
-    message: String,
-    details: Option<HashMap<String, String>>,
+    pub code: String,
+    pub message: String,
+    pub details: Option<HashMap<String, String>>,
+}
+
+impl std::fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error [{}]: {}", self.code, self.message)
+    }
 }
 
 /// Manages the chat connection and state
@@ -87,6 +137,23 @@ pub struct ChatManager {
     connection: Arc<Mutex<TheaterConnection>>,
     pub actor_id: String,
     debug: bool,
+    /// Retry policy for failed completions
+    retry_policy: RetryPolicy,
+    /// Attempt number of the current generation (1 for the first try)
+    generation_attempt: u32,
+    /// MCP servers sent to the actor in `update_settings`
+    mcp_servers: Vec<McpServer>,
+    /// MCP servers left out of `update_settings`, with the reason
+    skipped_mcp_servers: Vec<(String, String)>,
+    /// Set once `get_settings` has timed out, so later calls fail at once instead
+    /// of waiting for `SETTINGS_TIMEOUT` again
+    settings_unanswered: AtomicBool,
+    /// The actor's settings read back once after `update_settings`, or why they
+    /// could not be
+    settings: std::result::Result<ActorSettings, String>,
+    /// `confirm_tools` globs sent as `denied_tools` because the actor cannot hold
+    /// calls for approval
+    pub denied_confirm_tools: Vec<String>,
 }
 
 impl ChatManager {
@@ -116,15 +183,15 @@ impl ChatManager {
     /// Start the chat-state actor
     pub async fn start_actor(
         connection: &mut TheaterConnection,
-        _args: &CompatibleArgs,
+        args: &CompatibleArgs,
     ) -> Result<TheaterId> {
-        Self::start_actor_with_session(connection, _args, None).await
+        Self::start_actor_with_session(connection, args, None).await
     }
 
     /// Start the chat-state actor with optional session data
     pub async fn start_actor_with_session(
         connection: &mut TheaterConnection,
-        _args: &CompatibleArgs,
+        args: &CompatibleArgs,
         session_data: Option<&crate::persistence::SessionData>,
     ) -> Result<TheaterId> {
         info!("Starting chat-state actor");
@@ -151,10 +218,10 @@ impl ChatManager {
         // Start chat-state actor
         info!(
             "Starting chat-state actor with manifest: {}",
-            CHAT_STATE_ACTOR_MANIFEST
+            args.actor_manifest
         );
         let start_actor_cmd = ManagementCommand::StartActor {
-            manifest: CHAT_STATE_ACTOR_MANIFEST.to_string(),
+            manifest: args.actor_manifest.clone(),
             initial_state,
             parent: false,
             subscribe: false,
@@ -259,6 +326,23 @@ impl ChatManager {
             }
         };
 
+        // Resolve commands and placeholders so the server can launch each MCP server
+        let mut skipped_mcp_servers = Vec::new();
+        let mcp_servers: Vec<McpServer> = mcp_servers
+            .into_iter()
+            .filter_map(|mut server: McpServer| match server.config.resolve() {
+                Ok(config) => {
+                    server.config = config;
+                    Some(server)
+                }
+                Err(e) => {
+                    warn!("Skipping MCP server: {:?}", e);
+                    skipped_mcp_servers.push((server.display_name(), e.to_string()));
+                    None
+                }
+            })
+            .collect();
+
         // Configure the actor with settings
         info!("Configuring actor with settings...");
         let settings = if let Some(conversation_config) = config {
@@ -271,7 +355,10 @@ impl ChatManager {
                     "max_tokens": conversation_config.max_tokens,
                     "system_prompt": conversation_config.system_prompt,
                     "title": conversation_config.title,
-                    "mcp_servers": mcp_servers
+                    "mcp_servers": mcp_servers,
+                    "allowed_tools": conversation_config.allowed_tools,
+                    "denied_tools": conversation_config.denied_tools,
+                    "confirm_tools": conversation_config.confirm_tools
                 }
             })
         } else {
@@ -292,12 +379,72 @@ impl ChatManager {
             })
         };
         debug!("Settings payload: {:?}", settings);
+        Self::send_settings(&mut connection, &actor_id, &settings).await?;
+
+        let connection = Arc::new(Mutex::new(connection));
+
+        info!(
+            "ChatManager created successfully with actor ID: {}",
+            actor_id
+        );
+        let mut manager = ChatManager {
+            connection,
+            actor_id: actor_id.to_string(),
+            debug: args.debug,
+            retry_policy: config.map(|c| c.retry.clone()).unwrap_or_default(),
+            generation_attempt: 1,
+            mcp_servers,
+            skipped_mcp_servers,
+            settings_unanswered: AtomicBool::new(false),
+            settings: Err("Settings not read yet".to_string()),
+            denied_confirm_tools: Vec::new(),
+        };
+
+        // Read the settings back once: the tool inventory and the approval check
+        // both come from this reply, so an actor without get_settings is only
+        // waited on once
+        manager.settings = manager.get_settings().await.map_err(|e| {
+            warn!("Failed to read actor settings: {:?}", e);
+            format!("{:#}", e)
+        });
 
+        // An actor without tool approval would run calls matching confirm_tools
+        // unasked, so deny them instead
+        if let Some(conversation_config) = config {
+            let confirm_tools = &conversation_config.confirm_tools;
+            if !confirm_tools.is_empty() && !manager.supports_tool_approval() {
+                warn!(
+                    "The chat-state actor does not support tool approval; denying {:?}",
+                    confirm_tools
+                );
+                let mut denied_tools = conversation_config.denied_tools.clone();
+                denied_tools.extend(confirm_tools.iter().cloned());
+                let mut settings = settings;
+                settings["settings"]["denied_tools"] = json!(denied_tools);
+                settings["settings"]["confirm_tools"] = json!([]);
+
+                let mut conn = manager.connection.lock().await;
+                Self::send_settings(&mut conn, &actor_id, &settings)
+                    .await
+                    .context("Failed to deny confirm_tools on an actor without tool approval")?;
+                drop(conn);
+                manager.denied_confirm_tools = confirm_tools.clone();
+            }
+        }
+        Ok(manager)
+    }
+
+    /// Send `update_settings` and wait for the actor to confirm it
+    async fn send_settings(
+        connection: &mut TheaterConnection,
+        actor_id: &TheaterId,
+        settings: &serde_json::Value,
+    ) -> Result<()> {
         info!("Sending settings to actor...");
         connection
             .send(ManagementCommand::RequestActorMessage {
                 id: actor_id.clone(),
-                data: serde_json::to_vec(&settings).context("Failed to serialize settings")?,
+                data: serde_json::to_vec(settings).context("Failed to serialize settings")?,
             })
             .await
             .context("Failed to send settings to actor")?;
@@ -313,7 +460,7 @@ impl ChatManager {
             match response {
                 ManagementResponse::RequestedMessage { .. } => {
                     info!("Settings configured successfully");
-                    break;
+                    return Ok(());
                 }
                 ManagementResponse::Error { error } => {
                     error!("Failed to configure actor: {:?}", error);
@@ -324,18 +471,16 @@ impl ChatManager {
                 }
             }
         }
+    }
 
-        let connection = Arc::new(Mutex::new(connection));
-
-        info!(
-            "ChatManager created successfully with actor ID: {}",
-            actor_id
-        );
-        Ok(ChatManager {
-            connection,
-            actor_id: actor_id.to_string(),
-            debug: args.debug,
-        })
+    /// Start a fresh chat-state actor with a new conversation and configure it
+    pub async fn start_new_conversation(
+        args: &CompatibleArgs,
+        config: &crate::config_manager::ConversationConfig,
+    ) -> Result<Self> {
+        let mut connection = Self::connect_to_server(args).await?;
+        let actor_id = Self::start_actor_with_session(&mut connection, args, None).await?;
+        Self::open_channel_with_config(connection, actor_id, args, Some(config)).await
     }
 
     /// Create a new chat manager and initialize the connection (deprecated - use stepped approach)
@@ -517,8 +662,38 @@ impl ChatManager {
         Ok(messages)
     }
 
+    /// Retry policy applied to failed completions
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Decide whether a failed generation should be retried. Returns the delay and the
+    /// number of the upcoming attempt, or None when the error is not retryable or the
+    /// attempts are exhausted.
+    pub fn schedule_retry(&mut self, error: &ErrorInfo) -> Option<(std::time::Duration, u32)> {
+        if !self.retry_policy.is_retryable(&error.code)
+            || self.generation_attempt >= self.retry_policy.max_attempts
+        {
+            return None;
+        }
+
+        self.generation_attempt += 1;
+        let delay = self.retry_policy.backoff_for(self.generation_attempt);
+        info!(
+            "Scheduling retry {}/{} in {:?} after error {}",
+            self.generation_attempt, self.retry_policy.max_attempts, delay, error.code
+        );
+        Some((delay, self.generation_attempt))
+    }
+
+    /// Reset the attempt counter once a generation succeeds or a new turn starts
+    pub fn reset_retries(&mut self) {
+        self.generation_attempt = 1;
+    }
+
     pub async fn send_message(&mut self, message: String) -> Result<()> {
         info!("Sending message: {}", message);
+        self.reset_retries();
 
         let actor_id_parsed: TheaterId =
             self.actor_id.parse().context("Failed to parse actor ID")?;
@@ -572,6 +747,37 @@ impl ChatManager {
         Ok(())
     }
 
+    /// Answer a `tool_approval_request`, letting the actor run or skip the tool call
+    pub async fn send_tool_decision(&self, tool_use_id: &str, approved: bool) -> Result<()> {
+        info!(
+            "Sending tool decision for {}: {}",
+            tool_use_id,
+            if approved { "approved" } else { "denied" }
+        );
+
+        let actor_id_parsed: TheaterId =
+            self.actor_id.parse().context("Failed to parse actor ID")?;
+
+        let decision_request = json!({
+            "type": "tool_approval",
+            "tool_use_id": tool_use_id,
+            "approved": approved
+        });
+
+        {
+            let mut conn = self.connection.lock().await;
+            conn.send(ManagementCommand::RequestActorMessage {
+                id: actor_id_parsed,
+                data: serde_json::to_vec(&decision_request)
+                    .context("Failed to serialize tool decision")?,
+            })
+            .await
+            .context("Failed to send tool decision")?;
+        }
+
+        Ok(())
+    }
+
     /// Send a message and return the new head (don't fetch messages here)
     pub async fn send_message_get_head(&mut self, message: String) -> Result<String> {
         info!("Sending message and getting new head");
@@ -782,6 +988,119 @@ impl ChatManager {
         }
     }
 
+    /// Get the actor's current settings. Fails when the actor does not answer
+    /// `get_settings` within `SETTINGS_TIMEOUT`, and without asking once it has not.
+    pub async fn get_settings(&self) -> Result<ActorSettings> {
+        if self.settings_unanswered.load(Ordering::Relaxed) {
+            anyhow::bail!(
+                "The chat-state actor did not answer get_settings earlier in this session"
+            );
+        }
+        match tokio::time::timeout(SETTINGS_TIMEOUT, self.request_settings()).await {
+            Ok(result) => result,
+            Err(_) => {
+                self.settings_unanswered.store(true, Ordering::Relaxed);
+                Err(anyhow::anyhow!(
+                    "The chat-state actor did not answer get_settings within {}s (it may not support it)",
+                    SETTINGS_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    }
+
+    async fn request_settings(&self) -> Result<ActorSettings> {
+        let actor_id_parsed: TheaterId =
+            self.actor_id.parse().context("Failed to parse actor ID")?;
+
+        let settings_request = json!({
+            "type": "get_settings"
+        });
+
+        // Send the settings request
+        {
+            let mut conn = self.connection.lock().await;
+            conn.send(ManagementCommand::RequestActorMessage {
+                id: actor_id_parsed,
+                data: serde_json::to_vec(&settings_request)
+                    .context("Failed to serialize settings request")?,
+            })
+            .await
+            .context("Failed to send settings request")?;
+        }
+
+        // Wait for the response
+        loop {
+            let mut conn = self.connection.lock().await;
+            let response = conn.receive().await?;
+            match response {
+                ManagementResponse::RequestedMessage { message, .. } => {
+                    let response: ChatStateResponse = serde_json::from_slice(&message)
+                        .context("Failed to parse settings response")?;
+
+                    match response {
+                        ChatStateResponse::Settings { settings } => {
+                            info!(
+                                "Received settings - {} MCP servers",
+                                settings.mcp_servers.len()
+                            );
+                            return Ok(settings);
+                        }
+                        ChatStateResponse::Error { error } => {
+                            return Err(anyhow::anyhow!("Error getting settings: {:?}", error));
+                        }
+                        _ => continue,
+                    }
+                }
+                ManagementResponse::Error { error } => {
+                    return Err(anyhow::anyhow!("Error getting settings: {:?}", error));
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Whether the actor holds tool calls for approval (`tool_approval_request` and
+    /// `tool_approval`), which it shows by reporting `confirm_tools` in its settings
+    pub fn supports_tool_approval(&self) -> bool {
+        self.settings
+            .as_ref()
+            .map_or(false, |settings| settings.confirm_tools.is_some())
+    }
+
+    /// The tool inventory from the settings read when the actor was configured
+    pub fn initial_tool_inventory(&self) -> ToolInventory {
+        self.build_tool_inventory(self.settings.as_ref().map_err(String::clone))
+    }
+
+    /// Every configured MCP server with its status and the tools the actor reported,
+    /// asking the actor again. Failing to reach the actor is recorded in the
+    /// inventory rather than returned.
+    pub async fn tool_inventory(&self) -> ToolInventory {
+        match self.get_settings().await {
+            Ok(settings) => self.build_tool_inventory(Ok(&settings)),
+            Err(e) => {
+                warn!("Failed to read actor settings: {:?}", e);
+                self.build_tool_inventory(Err(format!("{:#}", e)))
+            }
+        }
+    }
+
+    fn build_tool_inventory(
+        &self,
+        settings: std::result::Result<&ActorSettings, String>,
+    ) -> ToolInventory {
+        match settings {
+            Ok(settings) => ToolInventory::build(
+                &self.mcp_servers,
+                &self.skipped_mcp_servers,
+                Some(settings.mcp_servers.as_slice()),
+            ),
+            Err(error) => {
+                ToolInventory::unavailable(&self.mcp_servers, &self.skipped_mcp_servers, error)
+            }
+        }
+    }
+
     /// Cleanup resources
     pub async fn cleanup(&self) -> Result<()> {
         let actor_id_parsed: TheaterId = self
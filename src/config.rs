@@ -1,5 +1,10 @@
 use clap::Parser;
 use ratatui;
+use std::collections::HashMap;
+
+use crate::config_format::ConfigFormat;
+use crate::config_layers::ConfigOverrides;
+use crate::config_manager::{ConfigLoadOptions, ConfigScope, ModelPricing};
 
 /// Configuration for tool display modes
 #[derive(Debug, Clone, PartialEq)]
@@ -45,10 +50,11 @@ pub struct LoadingStep {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum StepStatus {
-    Pending,        // Not started yet
-    InProgress,     // Currently running
-    Success,        // Completed successfully
-    Failed(String), // Failed with error message
+    Pending,         // Not started yet
+    InProgress,      // Currently running
+    Success,         // Completed successfully
+    Warning(String), // Completed with a warning message
+    Failed(String),  // Failed with error message
 }
 
 impl StepStatus {
@@ -57,6 +63,7 @@ impl StepStatus {
             StepStatus::Pending => "    ",
             StepStatus::InProgress => "[WAIT]",
             StepStatus::Success => "[ OK ]",
+            StepStatus::Warning(_) => "[WARN]",
             StepStatus::Failed(_) => "[FAIL]",
         }
     }
@@ -66,6 +73,7 @@ impl StepStatus {
             StepStatus::Pending => ratatui::style::Color::DarkGray,
             StepStatus::InProgress => ratatui::style::Color::Yellow,
             StepStatus::Success => ratatui::style::Color::Green,
+            StepStatus::Warning(_) => ratatui::style::Color::Yellow,
             StepStatus::Failed(_) => ratatui::style::Color::Red,
         }
     }
@@ -138,11 +146,57 @@ pub struct Args {
     #[clap(short = 'U', long, default_value = "false")]
     pub use_default_session: bool,
 
+    /// Chat-state actor manifest (path, URL or registry:<name>), overrides the config
+    #[clap(long, env = "TH_CHAT_ACTOR_MANIFEST", value_name = "MANIFEST")]
+    pub actor_manifest: Option<String>,
+
+    /// Model to use, overrides the config
+    #[clap(long, env = "THEATER_CHAT_MODEL")]
+    pub model: Option<String>,
+
+    /// Provider to use, overrides the config
+    #[clap(long, env = "THEATER_CHAT_PROVIDER")]
+    pub provider: Option<String>,
+
+    /// Sampling temperature, overrides the config
+    #[clap(long)]
+    pub temperature: Option<f32>,
+
+    /// Maximum tokens per completion, overrides the config
+    #[clap(long)]
+    pub max_tokens: Option<u32>,
+
+    /// System prompt, overrides the config
+    #[clap(long, env = "THEATER_CHAT_SYSTEM_PROMPT")]
+    pub system_prompt: Option<String>,
+
     /// Subcommands for management operations
     #[clap(subcommand)]
     pub command: Option<Command>,
 }
 
+impl Args {
+    /// Configuration overrides given on the command line
+    pub fn config_overrides(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            model: self.model.clone(),
+            provider: self.provider.clone(),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            system_prompt: self.system_prompt.clone(),
+        }
+    }
+
+    /// Options for loading the configuration layers selected by these arguments
+    pub fn config_load_options(&self) -> ConfigLoadOptions {
+        ConfigLoadOptions {
+            config_file: self.config.clone(),
+            preset: self.preset.clone(),
+            overrides: self.config_overrides(),
+        }
+    }
+}
+
 /// Management subcommands
 #[derive(Parser, Debug, Clone)]
 pub enum Command {
@@ -151,20 +205,211 @@ pub enum Command {
         /// Create global configuration instead of local
         #[clap(short = 'g', long)]
         global: bool,
+        /// Prompt for provider, model, system prompt and MCP servers
+        #[clap(short = 'i', long)]
+        interactive: bool,
+    },
+    /// List and manage presets
+    Presets {
+        #[clap(subcommand)]
+        action: Option<PresetAction>,
     },
-    /// List available presets
-    Presets,
     /// Manage chat sessions
     Sessions {
         #[clap(subcommand)]
         action: SessionAction,
     },
-    /// Show resolved configuration
+    /// Manage named MCP server definitions in .th-chat/mcp/
+    Mcp {
+        #[clap(subcommand)]
+        action: McpAction,
+    },
+    /// Check the Theater server, actor manifest, MCP servers and config files
+    Doctor,
+    /// Show, check and edit configuration
     Config {
         /// Show configuration for specific preset
         #[clap(short = 'p', long)]
         preset: Option<String>,
+        /// Show which layer each field came from
+        #[clap(long)]
+        explain: bool,
+        #[clap(subcommand)]
+        action: Option<ConfigAction>,
+    },
+}
+
+/// Configuration subcommands
+#[derive(Parser, Debug, Clone)]
+pub enum ConfigAction {
+    /// Check config and preset files against the schema
+    Validate {
+        /// File to validate (defaults to every config and preset in use)
+        file: Option<std::path::PathBuf>,
+    },
+    /// Print the JSON Schema for config files
+    Schema,
+    /// Convert a config or preset file between JSON, TOML and YAML
+    Convert {
+        /// File to convert
+        file: std::path::PathBuf,
+        /// Target format (json, toml or yaml)
+        #[clap(short = 't', long)]
+        to: ConfigFormat,
+        /// Output path (defaults to the input path with the new extension)
+        #[clap(short = 'o', long)]
+        output: Option<std::path::PathBuf>,
+        /// Keep the original file instead of moving it to <file>.bak
+        #[clap(long)]
+        keep: bool,
+    },
+    /// Print a single field, e.g. `model_config.model`
+    Get {
+        /// Dotted field path (`mcp_servers[0].config.command`)
+        path: String,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Set a single field in a config or preset file (`-p` selects the preset)
+    Set {
+        /// Dotted field path (`mcp_servers[0].config.command`)
+        path: String,
+        /// New value, parsed as JSON when possible and as a string otherwise
+        value: String,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Open a config or preset file in $EDITOR, validating it on save
+    Edit {
+        /// Edit a preset instead of the main config file
+        #[clap(short = 'p', long)]
+        preset: Option<String>,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+}
+
+/// Preset management subcommands
+#[derive(Parser, Debug, Clone)]
+pub enum PresetAction {
+    /// List available presets
+    List,
+    /// Print a preset file
+    Show {
+        /// Preset name
+        name: String,
+        /// Print the preset merged with the presets it extends
+        #[clap(short = 'r', long)]
+        resolved: bool,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Create a new preset
+    New {
+        /// Preset name
+        name: String,
+        /// Snapshot the current configuration as written (placeholders kept, defaults left out)
+        #[clap(short = 's', long)]
+        snapshot: bool,
+        /// Preset to inherit from
+        #[clap(short = 'e', long, conflicts_with = "snapshot")]
+        extends: Option<String>,
+        /// File format (json, toml or yaml)
+        #[clap(short = 'f', long, default_value = "json")]
+        format: ConfigFormat,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Copy a preset under a new name
+    Copy {
+        /// Existing preset
+        from: String,
+        /// New preset name
+        to: String,
+        /// Directory to copy into
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Delete a preset
+    Rm {
+        /// Preset name
+        name: String,
+        /// Force deletion without confirmation
+        #[clap(short = 'f', long)]
+        force: bool,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Show field-by-field differences between two resolved presets
+    Diff {
+        /// First preset
+        a: String,
+        /// Second preset
+        b: String,
+    },
+}
+
+/// MCP server management subcommands
+#[derive(Parser, Debug, Clone)]
+pub enum McpAction {
+    /// List named MCP server definitions
+    List,
+    /// Add a named MCP server definition
+    Add {
+        /// Name presets use to reference the server
+        name: String,
+        /// Server executable
+        command: String,
+        /// Arguments passed to the server
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+        /// Short description
+        #[clap(short = 'd', long)]
+        description: Option<String>,
+        /// Replace an existing definition
+        #[clap(short = 'f', long)]
+        force: bool,
+        #[clap(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Remove a named MCP server definition
+    Remove {
+        /// Definition name
+        name: String,
+        #[clap(flatten)]
+        scope: ScopeArgs,
     },
+    /// Start a server and list the tools it provides
+    Test {
+        /// Definition name, or the name of a server in the resolved config
+        name: String,
+        /// Seconds to wait for the server to respond
+        #[clap(short = 't', long, default_value = "10")]
+        timeout: u64,
+    },
+}
+
+/// Selects the local or global .th-chat directory
+#[derive(clap::Args, Debug, Clone)]
+pub struct ScopeArgs {
+    /// Use the global ~/.th-chat directory
+    #[clap(short = 'g', long, conflicts_with = "local")]
+    pub global: bool,
+    /// Use the local .th-chat directory
+    #[clap(short = 'l', long)]
+    pub local: bool,
+}
+
+impl ScopeArgs {
+    pub fn scope(&self) -> ConfigScope {
+        if self.global {
+            ConfigScope::Global
+        } else if self.local {
+            ConfigScope::Local
+        } else {
+            ConfigScope::Auto
+        }
+    }
 }
 
 /// Session management subcommands
@@ -233,8 +478,13 @@ pub struct CompatibleArgs {
     pub mcp_config: Option<String>,
 
     pub clear_session: bool,
+    /// Price table used to estimate token costs, keyed by model name
+    pub model_pricing: HashMap<String, ModelPricing>,
+    /// Context window of the configured model, if known
+    pub context_window: Option<u32>,
+    /// Resolved chat-state actor manifest
+    pub actor_manifest: String,
 }
 
-// Chat state actor manifest path
-pub const CHAT_STATE_ACTOR_MANIFEST: &str =
-    "/Users/colinrozzi/work/actor-registry/chat-state/manifest.toml";
+/// Fraction of the context window at which the chat view shows a warning banner
+pub const CONTEXT_WARNING_THRESHOLD: f64 = 0.8;
@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::config_format::ConfigFormat;
+use crate::config_schema::{validate_config_value, ValidationIssue};
+
+/// One step of a field path such as `mcp_servers[0].config.command`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parse a dotted field path. Keys containing dots can be quoted:
+/// `model_pricing."gemini-2.5-pro-preview-06-05".input_per_million`
+pub fn parse_field_path(path: &str) -> Result<Vec<PathSegment>> {
+    let mut segments = Vec::new();
+    let mut chars = path.chars();
+    let mut key = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    key.push(c);
+                }
+            }
+            '.' => {
+                if !key.is_empty() {
+                    segments.push(PathSegment::Key(std::mem::take(&mut key)));
+                }
+            }
+            '[' => {
+                if !key.is_empty() {
+                    segments.push(PathSegment::Key(std::mem::take(&mut key)));
+                }
+                let index: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                let index = index
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid index '[{}]' in '{}'", index, path))?;
+                segments.push(PathSegment::Index(index));
+            }
+            c => key.push(c),
+        }
+    }
+    if !key.is_empty() {
+        segments.push(PathSegment::Key(key));
+    }
+
+    if segments.is_empty() {
+        anyhow::bail!("Empty field path");
+    }
+    Ok(segments)
+}
+
+/// Look up a field in a config document
+pub fn get_field<'a>(value: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |current, segment| match segment {
+            PathSegment::Key(key) => current.get(key),
+            PathSegment::Index(index) => current.get(index),
+        })
+}
+
+/// Set a field in a config document, creating intermediate objects as needed.
+/// Array items must already exist, or be the next index (which appends).
+pub fn set_field(value: &mut Value, path: &[PathSegment], new_value: Value) -> Result<()> {
+    let Some((last, parents)) = path.split_last() else {
+        *value = new_value;
+        return Ok(());
+    };
+
+    let mut current = value;
+    for (position, segment) in parents.iter().enumerate() {
+        let next_is_index = matches!(path[position + 1], PathSegment::Index(_));
+        current = child_mut(current, segment, || {
+            if next_is_index {
+                Value::Array(Vec::new())
+            } else {
+                Value::Object(Map::new())
+            }
+        })?;
+    }
+
+    match last {
+        PathSegment::Key(key) => {
+            if current.is_null() {
+                *current = Value::Object(Map::new());
+            }
+            let map = current
+                .as_object_mut()
+                .with_context(|| format!("Cannot set '{}': parent is not an object", key))?;
+            map.insert(key.clone(), new_value);
+        }
+        PathSegment::Index(index) => {
+            let items = current
+                .as_array_mut()
+                .with_context(|| format!("Cannot set [{}]: parent is not an array", index))?;
+            match index.cmp(&items.len()) {
+                std::cmp::Ordering::Less => items[*index] = new_value,
+                std::cmp::Ordering::Equal => items.push(new_value),
+                std::cmp::Ordering::Greater => {
+                    anyhow::bail!("Index [{}] is out of range ({} items)", index, items.len())
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn child_mut<'a>(
+    current: &'a mut Value,
+    segment: &PathSegment,
+    empty: impl FnOnce() -> Value,
+) -> Result<&'a mut Value> {
+    match segment {
+        PathSegment::Key(key) => {
+            if current.is_null() {
+                *current = Value::Object(Map::new());
+            }
+            let map = current
+                .as_object_mut()
+                .with_context(|| format!("Cannot descend into '{}': not an object", key))?;
+            Ok(map.entry(key.clone()).or_insert_with(empty))
+        }
+        PathSegment::Index(index) => {
+            let items = current
+                .as_array_mut()
+                .with_context(|| format!("Cannot descend into [{}]: not an array", index))?;
+            if *index == items.len() {
+                items.push(empty());
+            }
+            let len = items.len();
+            items
+                .get_mut(*index)
+                .with_context(|| format!("Index [{}] is out of range ({} items)", index, len))
+        }
+    }
+}
+
+/// Interpret a command line value: valid JSON (numbers, booleans, null, arrays,
+/// objects, quoted strings) is used as-is, anything else is a plain string
+pub fn parse_cli_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Read a config document without expanding placeholders, so edits keep them.
+/// A missing file is an empty document.
+pub fn read_config_document(path: &Path) -> Result<Value> {
+    if !path.exists() {
+        return Ok(Value::Object(Map::new()));
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    ConfigFormat::from_path(path)
+        .parse(&content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))
+}
+
+/// Whether rewriting `path` with `write_config_document` would drop comments
+pub fn loses_comments(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| ConfigFormat::from_path(path).has_comments(&content))
+        .unwrap_or(false)
+}
+
+/// Validate a config document and write it in the format matching its extension
+pub fn write_config_document(path: &Path, value: &Value) -> Result<()> {
+    let issues = validate_config_value(value);
+    if !issues.is_empty() {
+        anyhow::bail!(
+            "Refusing to write invalid config {}:\n{}",
+            path.display(),
+            format_issues(&issues)
+        );
+    }
+
+    let content = ConfigFormat::from_path(path).serialize(value)?;
+    fs::write(path, content)
+        .with_context(|| format!("Failed to write config file: {}", path.display()))
+}
+
+/// One issue per line, indented for terminal output
+pub fn format_issues(issues: &[ValidationIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| format!("  {}", issue))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_field_path() {
+        assert_eq!(
+            parse_field_path("mcp_servers[0].config.command").unwrap(),
+            vec![
+                PathSegment::Key("mcp_servers".to_string()),
+                PathSegment::Index(0),
+                PathSegment::Key("config".to_string()),
+                PathSegment::Key("command".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_field_path("model_pricing.\"gemini-2.5-pro\".input_per_million").unwrap()[1],
+            PathSegment::Key("gemini-2.5-pro".to_string())
+        );
+        assert!(parse_field_path("").is_err());
+        assert!(parse_field_path("mcp_servers[x]").is_err());
+    }
+
+    #[test]
+    fn test_get_and_set_field() {
+        let mut value = json!({ "model_config": { "model": "a" }, "mcp_servers": [] });
+
+        let path = parse_field_path("model_config.model").unwrap();
+        set_field(&mut value, &path, parse_cli_value("b")).unwrap();
+        assert_eq!(get_field(&value, &path), Some(&json!("b")));
+
+        let path = parse_field_path("temperature").unwrap();
+        set_field(&mut value, &path, parse_cli_value("0.5")).unwrap();
+        assert_eq!(value["temperature"], json!(0.5));
+
+        let path = parse_field_path("mcp_servers[0].config.command").unwrap();
+        set_field(&mut value, &path, parse_cli_value("fs-mcp-server")).unwrap();
+        assert_eq!(
+            value["mcp_servers"][0]["config"]["command"],
+            "fs-mcp-server"
+        );
+
+        let path = parse_field_path("mcp_servers[5]").unwrap();
+        assert!(set_field(&mut value, &path, json!({})).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// File formats accepted for config and preset files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Config file extensions in discovery order: when several files share a name,
+/// the first extension wins
+pub const CONFIG_EXTENSIONS: &[&str] = &["json", "toml", "yaml", "yml"];
+
+impl ConfigFormat {
+    /// Detect the format from a file extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "toml" => Some(ConfigFormat::Toml),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Detect the format of a file, treating unknown extensions as JSON
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+            .unwrap_or(ConfigFormat::Json)
+    }
+
+    /// Preferred file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+        }
+    }
+
+    /// Parse a document into a JSON value
+    pub fn parse(&self, text: &str) -> Result<Value> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(text).context("Invalid JSON"),
+            ConfigFormat::Toml => toml::from_str(text).context("Invalid TOML"),
+            ConfigFormat::Yaml => serde_yaml::from_str(text).context("Invalid YAML"),
+        }
+    }
+
+    /// Serialize a JSON value in this format
+    pub fn serialize(&self, value: &Value) -> Result<String> {
+        match self {
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+            // TOML has no null; unset optional fields are simply left out
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(&without_nulls(value))?),
+            ConfigFormat::Yaml => Ok(serde_yaml::to_string(value)?),
+        }
+    }
+
+    /// Whether a document appears to contain comments, which `serialize` cannot
+    /// reproduce. Errs on the side of reporting: a `#` inside a string counts.
+    pub fn has_comments(&self, text: &str) -> bool {
+        match self {
+            ConfigFormat::Json => false,
+            ConfigFormat::Toml | ConfigFormat::Yaml => text
+                .lines()
+                .any(|line| line.trim_start().starts_with('#') || line.contains(" #")),
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_extension(s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown format '{}' (expected json, toml or yaml)", s))
+    }
+}
+
+impl std::fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Copy of a value with null object fields and array items removed
+fn without_nulls(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), without_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .filter(|item| !item.is_null())
+                .map(without_nulls)
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_manager::ConversationConfig;
+
+    #[test]
+    fn test_round_trip_formats() {
+        let config = ConversationConfig::default("/tmp/project".to_string());
+        let value = serde_json::to_value(&config).unwrap();
+
+        for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let text = format.serialize(&value).unwrap();
+            let parsed = format.parse(&text).unwrap();
+            let round_tripped: ConversationConfig = serde_json::from_value(parsed).unwrap();
+            assert_eq!(round_tripped.title, config.title);
+            assert_eq!(round_tripped.max_tokens, config.max_tokens);
+            assert_eq!(round_tripped.mcp_servers.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_toml_drops_nulls() {
+        let value = serde_json::json!({ "title": null, "tools": ["a", null, "b"] });
+        let text = ConfigFormat::Toml.serialize(&value).unwrap();
+        assert_eq!(
+            ConfigFormat::Toml.parse(&text).unwrap(),
+            serde_json::json!({ "tools": ["a", "b"] })
+        );
+    }
+
+    #[test]
+    fn test_key_order_preserved() {
+        for (format, text) in [
+            (ConfigFormat::Json, "{\"zeta\": 1, \"alpha\": 2}"),
+            (ConfigFormat::Toml, "zeta = 1\nalpha = 2\n"),
+            (ConfigFormat::Yaml, "zeta: 1\nalpha: 2\n"),
+        ] {
+            let written = format.serialize(&format.parse(text).unwrap()).unwrap();
+            assert!(written.find("zeta").unwrap() < written.find("alpha").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_has_comments() {
+        assert!(ConfigFormat::Toml.has_comments("# model\nmax_tokens = 10\n"));
+        assert!(ConfigFormat::Yaml.has_comments("max_tokens: 10 # tokens\n"));
+        assert!(!ConfigFormat::Toml.has_comments("max_tokens = 10\n"));
+        assert!(!ConfigFormat::Json.has_comments("{\"title\": \"#1\"}"));
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("presets/base.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config")),
+            ConfigFormat::Json
+        );
+        assert!("xml".parse::<ConfigFormat>().is_err());
+    }
+}
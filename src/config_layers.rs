@@ -0,0 +1,476 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config_manager::{ConfigSource, ConversationConfig};
+
+/// Field holding the MCP server list, which is merged by server name
+const MCP_SERVERS_FIELD: &str = "mcp_servers";
+
+/// A single configuration layer: a (possibly partial) config document and where it came from
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    pub source: ConfigSource,
+    pub value: Value,
+}
+
+impl ConfigLayer {
+    pub fn new(source: ConfigSource, value: Value) -> Self {
+        Self { source, value }
+    }
+}
+
+/// Command line overrides, applied on top of every other layer
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub system_prompt: Option<String>,
+}
+
+impl ConfigOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.to_value()
+            .as_object()
+            .map_or(true, |map| map.is_empty())
+    }
+
+    /// Partial config document containing only the overridden fields
+    pub fn to_value(&self) -> Value {
+        let mut root = Map::new();
+        let mut model_config = Map::new();
+        if let Some(model) = &self.model {
+            model_config.insert("model".to_string(), Value::from(model.clone()));
+        }
+        if let Some(provider) = &self.provider {
+            model_config.insert("provider".to_string(), Value::from(provider.clone()));
+        }
+        if !model_config.is_empty() {
+            root.insert("model_config".to_string(), Value::Object(model_config));
+        }
+        if let Some(temperature) = self.temperature {
+            root.insert("temperature".to_string(), Value::from(temperature));
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            root.insert("max_tokens".to_string(), Value::from(max_tokens));
+        }
+        if let Some(system_prompt) = &self.system_prompt {
+            root.insert(
+                "system_prompt".to_string(),
+                Value::from(system_prompt.clone()),
+            );
+            // An explicit prompt replaces any prompt file from lower layers
+            root.insert("system_prompt_file".to_string(), Value::Null);
+        }
+        Value::Object(root)
+    }
+}
+
+/// Result of merging layers: the merged document plus, for every field path,
+/// the index of the layer that last set it
+#[derive(Debug, Clone)]
+pub struct MergedLayers {
+    pub value: Value,
+    pub provenance: BTreeMap<String, usize>,
+}
+
+impl MergedLayers {
+    /// Index of the layer that set `path`, falling back to the closest parent path
+    pub fn layer_for(&self, path: &str) -> Option<usize> {
+        let mut candidate = path;
+        loop {
+            if let Some(index) = self.provenance.get(candidate) {
+                return Some(*index);
+            }
+            let cut = candidate.rfind(['.', '['])?;
+            candidate = &candidate[..cut];
+        }
+    }
+
+    /// Leaf fields of the merged document in display order, as (path, value) pairs.
+    /// Arrays are leaves, except MCP servers which are listed one per server.
+    pub fn fields(&self) -> Vec<(String, Value)> {
+        let mut fields = Vec::new();
+        collect_fields(&self.value, String::new(), &mut fields);
+        fields
+    }
+}
+
+/// A configuration built from merged layers, keeping track of where each field came from
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: ConversationConfig,
+    pub layers: Vec<ConfigLayer>,
+    pub merged: MergedLayers,
+}
+
+impl LayeredConfig {
+    /// Overall source: the single layer used, or every layer in priority order
+    pub fn source(&self) -> ConfigSource {
+        match self.layers.as_slice() {
+            [layer] => layer.source.clone(),
+            layers => ConfigSource::Layered(layers.iter().map(|l| l.source.clone()).collect()),
+        }
+    }
+
+    /// Directory that relative paths in the field at `path` resolve against, taken
+    /// from the layer that set it; `None` for built-in defaults
+    pub fn base_dir_for(&self, path: &str) -> Option<PathBuf> {
+        let index = self.merged.layer_for(path)?;
+        self.layers.get(index)?.source.base_dir()
+    }
+
+    /// Every field of the merged configuration with the layer that set it
+    pub fn explain(&self) -> Vec<(String, Value, Option<&ConfigSource>)> {
+        self.merged
+            .fields()
+            .into_iter()
+            .map(|(path, value)| {
+                let source = self
+                    .merged
+                    .layer_for(&path)
+                    .and_then(|index| self.layers.get(index))
+                    .map(|layer| &layer.source);
+                (path, value, source)
+            })
+            .collect()
+    }
+}
+
+/// Deep-merge layers in order, later layers winning.
+///
+/// Objects merge field by field; scalars and arrays replace. MCP servers are merged
+/// by name, and the built-in default servers are dropped as soon as any other
+/// layer defines its own `mcp_servers`.
+pub fn merge_layers(layers: &[ConfigLayer]) -> MergedLayers {
+    let mut merged = MergedLayers {
+        value: Value::Object(Map::new()),
+        provenance: BTreeMap::new(),
+    };
+
+    let overrides_default_servers = layers.iter().any(|layer| {
+        !matches!(layer.source, ConfigSource::Default)
+            && layer.value.get(MCP_SERVERS_FIELD).is_some()
+    });
+
+    for (index, layer) in layers.iter().enumerate() {
+        let mut value = layer.value.clone();
+        if overrides_default_servers && matches!(layer.source, ConfigSource::Default) {
+            if let Some(map) = value.as_object_mut() {
+                map.remove(MCP_SERVERS_FIELD);
+            }
+        }
+        merge_value(&mut merged, &[], value, index);
+    }
+
+    merged
+}
+
+/// How a field differs between two config documents
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldChange {
+    Added(Value),
+    Removed(Value),
+    Changed(Value, Value),
+}
+
+/// Field-by-field differences from `before` to `after`, using the same field paths
+/// as `config --explain`
+pub fn diff_fields(before: &Value, after: &Value) -> Vec<(String, FieldChange)> {
+    let mut before_fields = Vec::new();
+    collect_fields(before, String::new(), &mut before_fields);
+    let mut after_fields = Vec::new();
+    collect_fields(after, String::new(), &mut after_fields);
+
+    let before_paths: BTreeMap<&str, &Value> = before_fields
+        .iter()
+        .map(|(path, value)| (path.as_str(), value))
+        .collect();
+    let after_paths: BTreeMap<&str, &Value> = after_fields
+        .iter()
+        .map(|(path, value)| (path.as_str(), value))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (path, old) in &before_fields {
+        match after_paths.get(path.as_str()) {
+            Some(new) if *new == old => {}
+            Some(new) => changes.push((
+                path.clone(),
+                FieldChange::Changed(old.clone(), (*new).clone()),
+            )),
+            None => changes.push((path.clone(), FieldChange::Removed(old.clone()))),
+        }
+    }
+    for (path, new) in &after_fields {
+        if !before_paths.contains_key(path.as_str()) {
+            changes.push((path.clone(), FieldChange::Added(new.clone())));
+        }
+    }
+    changes
+}
+
+fn merge_value(merged: &mut MergedLayers, path: &[String], overlay: Value, layer: usize) {
+    match overlay {
+        Value::Object(overlay_map) => {
+            if !matches!(lookup_mut(&mut merged.value, path), Some(Value::Object(_))) {
+                set_value(merged, path, Value::Object(Map::new()), layer);
+            }
+            for (key, value) in overlay_map {
+                if path.is_empty() && key == MCP_SERVERS_FIELD {
+                    merge_mcp_servers(merged, value, layer);
+                } else {
+                    let mut child = path.to_vec();
+                    child.push(key);
+                    merge_value(merged, &child, value, layer);
+                }
+            }
+        }
+        value => set_value(merged, path, value, layer),
+    }
+}
+
+/// Merge MCP server lists, replacing servers with the same name and appending new ones
+fn merge_mcp_servers(merged: &mut MergedLayers, overlay: Value, layer: usize) {
+    let field = [MCP_SERVERS_FIELD.to_string()];
+    let Value::Array(servers) = overlay else {
+        set_value(merged, &field, overlay, layer);
+        return;
+    };
+
+    let mut current = match lookup_mut(&mut merged.value, &field) {
+        Some(Value::Array(existing)) => std::mem::take(existing),
+        _ => Vec::new(),
+    };
+
+    for server in servers {
+        let name = mcp_server_name(&server);
+        match current
+            .iter()
+            .position(|existing| mcp_server_name(existing) == name)
+        {
+            Some(position) => current[position] = server,
+            None => current.push(server),
+        }
+        merged
+            .provenance
+            .insert(format!("{}[{}]", MCP_SERVERS_FIELD, name), layer);
+    }
+
+    if let Some(map) = merged.value.as_object_mut() {
+        map.insert(MCP_SERVERS_FIELD.to_string(), Value::Array(current));
+    }
+}
+
+/// Name used to match MCP servers across layers: the explicit `name`, or the
+/// command's file name. A definition reference is matched by the name it refers to.
+pub fn mcp_server_name(server: &Value) -> String {
+    // An unexpanded reference to a definition in mcp/
+    if let Some(reference) = server.as_str() {
+        return reference.to_string();
+    }
+    if let Some(name) = server.get("name").and_then(Value::as_str) {
+        return name.to_string();
+    }
+    server
+        .pointer("/config/command")
+        .and_then(Value::as_str)
+        .map(|command| {
+            Path::new(command)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| command.to_string())
+        })
+        .unwrap_or_default()
+}
+
+fn set_value(merged: &mut MergedLayers, path: &[String], value: Value, layer: usize) {
+    let Some((key, parent)) = path.split_last() else {
+        merged.value = value;
+        return;
+    };
+
+    // Drop provenance of anything this value replaces
+    let display = path.join(".");
+    let prefix_dot = format!("{}.", display);
+    let prefix_index = format!("{}[", display);
+    merged
+        .provenance
+        .retain(|field, _| !field.starts_with(&prefix_dot) && !field.starts_with(&prefix_index));
+    merged.provenance.insert(display, layer);
+
+    if let Some(Value::Object(map)) = lookup_mut(&mut merged.value, parent) {
+        map.insert(key.clone(), value);
+    }
+}
+
+fn lookup_mut<'a>(value: &'a mut Value, path: &[String]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(value, |current, key| current.as_object_mut()?.get_mut(key))
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn collect_fields(value: &Value, path: String, fields: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                collect_fields(child, join_path(&path, key), fields);
+            }
+        }
+        Value::Array(servers) if path == MCP_SERVERS_FIELD => {
+            for server in servers {
+                fields.push((
+                    format!("{}[{}]", MCP_SERVERS_FIELD, mcp_server_name(server)),
+                    server.clone(),
+                ));
+            }
+        }
+        _ => fields.push((path, value.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directory::ThChatDirectory;
+    use serde_json::json;
+
+    fn file_layer(name: &str, value: Value) -> ConfigLayer {
+        ConfigLayer::new(ConfigSource::File(PathBuf::from(name)), value)
+    }
+
+    #[test]
+    fn test_merge_layers_deep_merge_and_provenance() {
+        let layers = vec![
+            ConfigLayer::new(
+                ConfigSource::Default,
+                json!({
+                    "model_config": { "model": "default-model", "provider": "google" },
+                    "max_tokens": 1000,
+                    "mcp_servers": [{ "config": { "command": "/bin/default-server", "args": [] } }]
+                }),
+            ),
+            file_layer(
+                "global.json",
+                json!({
+                    "model_config": { "model": "global-model" },
+                    "mcp_servers": [{ "name": "fs", "config": { "command": "fs", "args": ["a"] } }]
+                }),
+            ),
+            file_layer(
+                "local.json",
+                json!({
+                    "max_tokens": 2000,
+                    "mcp_servers": [
+                        { "name": "fs", "config": { "command": "fs", "args": ["b"] } },
+                        { "config": { "command": "/usr/bin/git-server", "args": [] } }
+                    ]
+                }),
+            ),
+        ];
+
+        let merged = merge_layers(&layers);
+
+        assert_eq!(merged.value["model_config"]["model"], "global-model");
+        assert_eq!(merged.value["model_config"]["provider"], "google");
+        assert_eq!(merged.value["max_tokens"], 2000);
+
+        // Default servers are dropped, named servers merged, new servers appended
+        let servers = merged.value["mcp_servers"].as_array().unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0]["config"]["args"], json!(["b"]));
+        assert_eq!(mcp_server_name(&servers[1]), "git-server");
+
+        assert_eq!(merged.layer_for("model_config.model"), Some(1));
+        assert_eq!(merged.layer_for("model_config.provider"), Some(0));
+        assert_eq!(merged.layer_for("max_tokens"), Some(2));
+        assert_eq!(merged.layer_for("mcp_servers[fs]"), Some(2));
+    }
+
+    #[test]
+    fn test_diff_fields() {
+        let before = json!({
+            "model_config": { "model": "a", "provider": "google" },
+            "temperature": 0.5,
+            "mcp_servers": [{ "name": "fs", "config": { "command": "fs", "args": [] } }]
+        });
+        let after = json!({
+            "model_config": { "model": "b", "provider": "google" },
+            "max_tokens": 100,
+            "mcp_servers": [{ "name": "fs", "config": { "command": "fs", "args": [] } }]
+        });
+
+        assert_eq!(
+            diff_fields(&before, &after),
+            vec![
+                (
+                    "model_config.model".to_string(),
+                    FieldChange::Changed(json!("a"), json!("b"))
+                ),
+                ("temperature".to_string(), FieldChange::Removed(json!(0.5))),
+                ("max_tokens".to_string(), FieldChange::Added(json!(100))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overrides_to_value() {
+        assert!(ConfigOverrides::default().is_empty());
+
+        let overrides = ConfigOverrides {
+            model: Some("m".to_string()),
+            max_tokens: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            overrides.to_value(),
+            json!({ "model_config": { "model": "m" }, "max_tokens": 10 })
+        );
+    }
+
+    #[test]
+    fn test_base_dir_for() {
+        let global = ThChatDirectory::new(PathBuf::from("/home/user/.th-chat"));
+        let local = ThChatDirectory::new(PathBuf::from("/project/.th-chat"));
+        let layers = vec![
+            ConfigLayer::new(ConfigSource::Default, json!({ "title": "Chat" })),
+            ConfigLayer::new(
+                ConfigSource::Global(global),
+                json!({ "actor_manifest": "actors/chat.toml" }),
+            ),
+            ConfigLayer::new(
+                ConfigSource::Local(local),
+                json!({ "system_prompt_file": "prompt.md" }),
+            ),
+            file_layer("/tmp/extra/config.json", json!({ "max_tokens": 10 })),
+        ];
+        let layered = LayeredConfig {
+            config: ConversationConfig::default(String::new()),
+            merged: merge_layers(&layers),
+            layers,
+        };
+
+        assert_eq!(
+            layered.base_dir_for("actor_manifest"),
+            Some(PathBuf::from("/home/user/.th-chat"))
+        );
+        assert_eq!(
+            layered.base_dir_for("system_prompt_file"),
+            Some(PathBuf::from("/project/.th-chat"))
+        );
+        assert_eq!(
+            layered.base_dir_for("max_tokens"),
+            Some(PathBuf::from("/tmp/extra"))
+        );
+        assert_eq!(layered.base_dir_for("title"), None);
+    }
+}
@@ -1,11 +1,20 @@
 use anyhow::{Context, Result};
 use mcp_protocol::tool::Tool;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use tracing::{debug, info, warn};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
 
+use crate::config_format::ConfigFormat;
+use crate::config_layers::{merge_layers, ConfigLayer, ConfigOverrides, LayeredConfig};
+use crate::config_schema::validate_config_text;
 use crate::directory::{find_th_chat_dir, get_global_th_chat_dir, ThChatDirectory};
+use crate::interpolation::{interpolate_json, rebase_file_placeholders_json};
+use crate::mcp_registry::{
+    definition_path, expand_mcp_references, list_definitions, load_definition, McpDefinition,
+};
+use crate::prompt_template::{render, resolve_prompt_path, TemplateContext};
 
 /// Full conversation settings - EXACT COPY from chat-state actor to ensure compatibility
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,17 +31,246 @@ pub struct ConversationConfig {
     /// System prompt to use
     pub system_prompt: Option<String>,
 
+    /// File holding the system prompt, relative to the .th-chat directory of the config
+    /// setting it; takes precedence over `system_prompt` (th-chat only, rendered at
+    /// session start)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt_file: Option<String>,
+
     /// Title of the conversation
     pub title: String,
 
     /// Mcp servers
     pub mcp_servers: Vec<McpServer>,
+
+    /// Per-model prices used to estimate session cost (th-chat only, not sent to the actor)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Automatic retry of failed completions (th-chat only, not sent to the actor)
+    #[serde(default)]
+    pub retry: RetryPolicy,
+
+    /// Chat-state actor manifest: a path (relative to the .th-chat directory of the
+    /// config setting it), a URL, or a `registry:<name>` reference (th-chat only, not sent to the actor)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor_manifest: Option<String>,
+
+    /// Globs of tool names the session may use; unset allows every tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+
+    /// Globs of tool names the session may not use; wins over `allowed_tools`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_tools: Vec<String>,
+
+    /// Globs of tool names the actor must get approval for in the TUI before running
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confirm_tools: Vec<String>,
+
+    /// Renderer (`json`, `text`, `table`, `diff` or `tree`) for tools by name or glob.
+    /// Only used by th-chat, never sent to the actor.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_renderers: HashMap<String, String>,
+}
+
+/// Actor manifest used when none is configured
+pub const DEFAULT_ACTOR_MANIFEST: &str = "registry:chat-state";
+
+/// Environment variable pointing at a local actor registry checkout
+pub const ACTOR_REGISTRY_ENV: &str = "THEATER_ACTOR_REGISTRY";
+
+/// Prefix for registry-style actor references
+const REGISTRY_PREFIX: &str = "registry:";
+
+/// Default actor registry location (~/.theater/actor-registry)
+pub fn default_actor_registry_dir() -> Option<PathBuf> {
+    std::env::var_os(ACTOR_REGISTRY_ENV)
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".theater").join("actor-registry")))
+}
+
+/// Resolve an actor manifest reference to a location the Theater server can load.
+///
+/// URLs are passed through unchanged, `registry:<name>` resolves to
+/// `<registry_dir>/<name>/manifest.toml`, and anything else is treated as a file path,
+/// resolved against `base_dir` when relative. Local manifests must exist.
+pub fn resolve_actor_manifest(
+    reference: &str,
+    base_dir: &Path,
+    registry_dir: Option<&Path>,
+) -> Result<String> {
+    let reference = reference.trim();
+    if reference.is_empty() {
+        anyhow::bail!("Actor manifest reference is empty");
+    }
+
+    if reference.contains("://") {
+        return Ok(reference.to_string());
+    }
+
+    let path = if let Some(name) = reference.strip_prefix(REGISTRY_PREFIX) {
+        let registry_dir = registry_dir.with_context(|| {
+            format!(
+                "Cannot resolve '{}': no actor registry found (set {})",
+                reference, ACTOR_REGISTRY_ENV
+            )
+        })?;
+        let entry = registry_dir.join(name);
+        if entry.extension().map_or(false, |ext| ext == "toml") {
+            entry
+        } else {
+            entry.join("manifest.toml")
+        }
+    } else {
+        let path = PathBuf::from(reference);
+        if path.is_relative() {
+            base_dir.join(path)
+        } else {
+            path
+        }
+    };
+
+    if !path.is_file() {
+        anyhow::bail!(
+            "Actor manifest '{}' not found at {}. Set `actor_manifest` in config.json, \
+             pass --actor-manifest, or set TH_CHAT_ACTOR_MANIFEST (registry references \
+             use {})",
+            reference,
+            path.display(),
+            ACTOR_REGISTRY_ENV
+        );
+    }
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Retry policy applied when generating a completion fails with a transient error
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one (1 disables retries)
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the delay between attempts
+    pub max_backoff_ms: u64,
+    /// Factor the delay grows by after each retry
+    pub backoff_multiplier: f64,
+    /// `ErrorInfo.code` values that are worth retrying
+    pub retryable_codes: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 2_000,
+            max_backoff_ms: 60_000,
+            backoff_multiplier: 2.0,
+            retryable_codes: [
+                "429",
+                "500",
+                "502",
+                "503",
+                "529",
+                "rate_limit_error",
+                "overloaded_error",
+            ]
+            .iter()
+            .map(|code| code.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether an error code should be retried
+    pub fn is_retryable(&self, code: &str) -> bool {
+        self.retryable_codes.iter().any(|c| c == code)
+    }
+
+    /// Delay before the given attempt (attempt 2 is the first retry)
+    pub fn backoff_for(&self, attempt: u32) -> std::time::Duration {
+        let retries = attempt.saturating_sub(2) as i32;
+        let delay = self.initial_backoff_ms as f64 * self.backoff_multiplier.max(1.0).powi(retries);
+        std::time::Duration::from_millis(delay.min(self.max_backoff_ms as f64) as u64)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ModelConfig {
     pub model: String,
     pub provider: String,
+
+    /// Context window size in tokens (falls back to a built-in table when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+}
+
+impl ModelConfig {
+    /// Context window for this model, from the config or the built-in table
+    pub fn effective_context_window(&self) -> Option<u32> {
+        self.context_window
+            .or_else(|| default_context_window(&self.model))
+    }
+}
+
+/// Built-in context window sizes for known model families
+pub fn default_context_window(model: &str) -> Option<u32> {
+    if model.starts_with("claude-") {
+        Some(200_000)
+    } else if ["gemini-2.5", "gemini-2.0", "gemini-1.5"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+    {
+        Some(1_048_576)
+    } else if model.starts_with("gpt-4o") {
+        Some(128_000)
+    } else {
+        None
+    }
+}
+
+/// Token prices for a model, in USD per million tokens
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPricing {
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    /// Estimate the cost in USD of the given token counts
+    pub fn estimate_cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_million
+            + output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Built-in price table for the models used by the generated presets
+pub fn default_model_pricing() -> HashMap<String, ModelPricing> {
+    let mut pricing = HashMap::new();
+    pricing.insert(
+        "claude-sonnet-4-20250514".to_string(),
+        ModelPricing::new(3.0, 15.0),
+    );
+    pricing.insert(
+        "gemini-2.5-flash-preview-04-17".to_string(),
+        ModelPricing::new(0.15, 0.6),
+    );
+    pricing.insert(
+        "gemini-2.5-pro-preview-06-05".to_string(),
+        ModelPricing::new(1.25, 10.0),
+    );
+    pricing
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -43,9 +281,90 @@ pub struct McpConfig {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct McpServer {
+    /// Name used to merge servers across config layers; defaults to the command's file name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub actor_id: Option<String>,
     pub config: McpConfig,
     pub tools: Option<Vec<Tool>>,
+    /// Globs of this server's tools that may be used; unset allows every tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    /// Globs of this server's tools that may not be used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_tools: Vec<String>,
+}
+
+impl McpServer {
+    /// The configured name, or the command's file name
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        Path::new(&self.config.command)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.config.command.clone())
+    }
+}
+
+impl McpConfig {
+    /// Resolve the command to an absolute path via `$PATH`, so the Theater server
+    /// can launch it regardless of its own environment. Placeholders are already
+    /// expanded when the config file is loaded.
+    pub fn resolve(&self) -> Result<McpConfig> {
+        let resolved = find_executable(&self.command)
+            .with_context(|| format!("MCP server command not found: {}", self.command))?;
+
+        Ok(McpConfig {
+            command: resolved.to_string_lossy().to_string(),
+            args: self.args.clone(),
+        })
+    }
+}
+
+/// A well-known MCP server that `th-chat init` looks for on `$PATH`
+#[derive(Debug, Clone, Copy)]
+pub struct KnownMcpServer {
+    /// Executable name
+    pub command: &'static str,
+    /// Short description shown by `th-chat init`
+    pub description: &'static str,
+}
+
+/// MCP servers the example presets are built from
+pub const KNOWN_MCP_SERVERS: &[KnownMcpServer] = &[
+    KnownMcpServer {
+        command: "simple-fs-mcp-server",
+        description: "Basic filesystem access",
+    },
+    KnownMcpServer {
+        command: "fs-mcp-server",
+        description: "Filesystem access with editing tools",
+    },
+];
+
+/// Known MCP servers whose executables are found on `$PATH`
+pub fn detect_installed_mcp_servers() -> Vec<KnownMcpServer> {
+    KNOWN_MCP_SERVERS
+        .iter()
+        .filter(|server| find_executable(server.command).is_some())
+        .copied()
+        .collect()
+}
+
+/// Locate an executable. Commands containing a path separator are checked as-is,
+/// bare names are looked up on `$PATH`.
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let search_path = std::env::var_os("PATH")?;
+    std::env::split_paths(&search_path)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_file())
 }
 
 impl ConversationConfig {
@@ -54,20 +373,63 @@ impl ConversationConfig {
             model_config: ModelConfig {
                 model: "gemini-2.5-flash-preview-04-17".to_string(),
                 provider: "google".to_string(),
+                context_window: None,
             },
             temperature: None,
             max_tokens: 65535,
             system_prompt: None,
+            system_prompt_file: None,
             title: "CLI Chat".to_string(),
             mcp_servers: vec![McpServer {
+                name: None,
                 actor_id: None,
                 config: McpConfig {
-                    command: "/Users/colinrozzi/work/mcp-servers/simple-fs-mcp/target/release/simple-fs-mcp-server".to_string(),
+                    command: "simple-fs-mcp-server".to_string(),
                     args: vec!["--allowed-dirs".to_string(), dir],
                 },
                 tools: None,
+                allowed_tools: None,
+                denied_tools: Vec::new(),
             }],
+            model_pricing: default_model_pricing(),
+            retry: RetryPolicy::default(),
+            actor_manifest: None,
+            allowed_tools: None,
+            denied_tools: Vec::new(),
+            confirm_tools: Vec::new(),
+            tool_renderers: HashMap::new(),
+        }
+    }
+}
+
+impl ConversationConfig {
+    /// Load `system_prompt_file` (if set) and render the prompt template, leaving
+    /// the final text in `system_prompt`. Relative paths resolve against `base_dir`.
+    pub fn render_system_prompt(
+        &mut self,
+        base_dir: &Path,
+        context: &TemplateContext,
+    ) -> Result<()> {
+        if let Some(file) = self.system_prompt_file.take() {
+            let path = resolve_prompt_path(&file, base_dir);
+            let template = fs::read_to_string(&path).with_context(|| {
+                format!("Failed to read system prompt file: {}", path.display())
+            })?;
+            let prompt_dir = path.parent().unwrap_or(base_dir);
+            self.system_prompt = Some(render(&template, context, prompt_dir)?);
+        } else if let Some(template) = &self.system_prompt {
+            self.system_prompt = Some(render(template, context, base_dir)?);
         }
+        Ok(())
+    }
+
+    /// MCP server commands that cannot be resolved, with the reason
+    pub fn unresolved_mcp_servers(&self) -> Vec<String> {
+        self.mcp_servers
+            .iter()
+            .filter_map(|server| server.config.resolve().err())
+            .map(|e| e.to_string())
+            .collect()
     }
 }
 
@@ -87,6 +449,10 @@ pub enum ConfigSource {
     },
     /// Explicit configuration file path
     File(std::path::PathBuf),
+    /// Command line flags
+    CommandLine,
+    /// Several layers merged together, lowest priority first
+    Layered(Vec<ConfigSource>),
 }
 
 impl std::fmt::Display for ConfigSource {
@@ -104,10 +470,132 @@ impl std::fmt::Display for ConfigSource {
                 )
             }
             ConfigSource::File(path) => write!(f, "config file ({})", path.display()),
+            ConfigSource::CommandLine => write!(f, "command line"),
+            ConfigSource::Layered(sources) => {
+                let sources: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
+                write!(f, "{}", sources.join(" + "))
+            }
+        }
+    }
+}
+
+impl ConfigSource {
+    /// Directory that relative paths set by this source resolve against: the .th-chat
+    /// directory for config files and presets, the file's directory for `--config`,
+    /// and the current directory for command line flags
+    pub fn base_dir(&self) -> Option<PathBuf> {
+        match self {
+            ConfigSource::Global(dir) | ConfigSource::Local(dir) => Some(dir.root.clone()),
+            ConfigSource::Preset { directory, .. } => Some(directory.root.clone()),
+            ConfigSource::File(path) => path.parent().map(Path::to_path_buf),
+            ConfigSource::CommandLine => std::env::current_dir().ok(),
+            ConfigSource::Default | ConfigSource::Layered(_) => None,
+        }
+    }
+
+    /// Directories relative paths in this source resolve against
+    pub fn path_bases(&self) -> Option<PathBases> {
+        match self {
+            ConfigSource::Preset { directory, .. } => Some(PathBases::for_preset(directory)),
+            ConfigSource::File(path) => Some(PathBases::for_file(path)),
+            _ => self.base_dir().map(|dir| PathBases {
+                fields: dir.clone(),
+                placeholders: dir,
+            }),
         }
     }
 }
 
+/// Config fields holding a path that resolves against `ConfigSource::base_dir`
+const PATH_FIELDS: &[&str] = &["system_prompt_file", "actor_manifest"];
+
+/// Directories the relative paths in a config file resolve against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathBases {
+    /// Base for `system_prompt_file` and `actor_manifest`
+    pub fields: PathBuf,
+    /// Base for `${file:...}` placeholders: the directory holding the file
+    pub placeholders: PathBuf,
+}
+
+impl PathBases {
+    /// Bases of a preset file in `directory`
+    pub fn for_preset(directory: &ThChatDirectory) -> Self {
+        Self {
+            fields: directory.root.clone(),
+            placeholders: directory.presets_dir.clone(),
+        }
+    }
+
+    /// Bases of a config file passed with `--config`
+    pub fn for_file(path: &Path) -> Self {
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self {
+            fields: dir.clone(),
+            placeholders: dir,
+        }
+    }
+}
+
+/// Rewrite the relative paths in a config document written for `from` so they point
+/// at the same files once it is written for `to`. Returns the fields rewritten.
+pub fn rebase_relative_paths(
+    value: &mut serde_json::Value,
+    from: &PathBases,
+    to: &PathBases,
+) -> Vec<String> {
+    let mut rewritten = Vec::new();
+    if from.fields != to.fields {
+        for field in PATH_FIELDS {
+            if let Some(serde_json::Value::String(path)) = value.get_mut(*field) {
+                if is_relative_path(path) {
+                    *path = from.fields.join(&*path).display().to_string();
+                    rewritten.push(field.to_string());
+                }
+            }
+        }
+    }
+    if from.placeholders != to.placeholders {
+        rewritten.extend(rebase_file_placeholders_json(value, &from.placeholders));
+    }
+    rewritten
+}
+
+/// A relative file path, rather than a home or absolute path, a URL, a registry
+/// reference or a value starting with a placeholder
+fn is_relative_path(reference: &str) -> bool {
+    let reference = reference.trim();
+    !reference.is_empty()
+        && !reference.starts_with(['~', '$'])
+        && !reference.contains("://")
+        && !reference.starts_with(REGISTRY_PREFIX)
+        && Path::new(reference).is_relative()
+}
+
+/// Which .th-chat directory a command writes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigScope {
+    /// The local directory if there is one, otherwise the global one
+    #[default]
+    Auto,
+    Local,
+    Global,
+}
+
+/// Layer holding the built-in defaults, with the current directory as the allowed
+/// directory of the default MCP server
+fn default_layer() -> Result<ConfigLayer> {
+    let dir = std::env::current_dir()
+        .map_err(|e| anyhow::anyhow!("Failed to get current directory: {}", e))?
+        .to_string_lossy()
+        .to_string();
+
+    Ok(ConfigLayer::new(
+        ConfigSource::Default,
+        serde_json::to_value(ConversationConfig::default(dir))?,
+    ))
+}
+
 /// Configuration manager handles loading and resolving configurations
 pub struct ConfigManager {
     /// Local .th-chat directory (if found)
@@ -142,103 +630,230 @@ impl ConfigManager {
         &self,
         options: &ConfigLoadOptions,
     ) -> Result<(ConversationConfig, ConfigSource)> {
-        // Priority order:
-        // 1. Explicit config file (--config)
-        // 2. Named preset (--preset)
-        // 3. Local project config (./.th-chat/config.json)
-        // 4. Global config (~/.th-chat/config.json)
-        // 5. Built-in defaults
+        let layered = self.load_layered(options)?;
+        let source = layered.source();
+        Ok((layered.config, source))
+    }
 
-        if let Some(config_file) = &options.config_file {
+    /// Load every configuration layer and deep-merge them into one configuration
+    pub fn load_layered(&self, options: &ConfigLoadOptions) -> Result<LayeredConfig> {
+        let layers = self.load_layers(options)?;
+        let merged = merge_layers(&layers);
+        let config: ConversationConfig = serde_json::from_value(merged.value.clone())
+            .context("Failed to build configuration from merged layers")?;
+
+        Ok(LayeredConfig {
+            config,
+            layers,
+            merged,
+        })
+    }
+
+    /// Collect configuration layers, lowest priority first:
+    /// 1. Built-in defaults
+    /// 2. Global config (~/.th-chat/config.json)
+    /// 3. Local project config (./.th-chat/config.json)
+    /// 4. Named preset (--preset)
+    /// 5. Explicit config file (--config)
+    /// 6. Command line flags
+    pub fn load_layers(&self, options: &ConfigLoadOptions) -> Result<Vec<ConfigLayer>> {
+        let mut layers = vec![default_layer()?];
+        layers.extend(self.file_layers(options, true)?);
+        Ok(layers)
+    }
+
+    /// Merge every layer except the built-in defaults as written, keeping `${...}`
+    /// placeholders and MCP server references unexpanded, e.g. to snapshot the
+    /// configuration into a preset without copying secrets or defaults. Relative
+    /// paths are rewritten to resolve from a file with the `target` bases.
+    pub fn load_unexpanded(
+        &self,
+        options: &ConfigLoadOptions,
+        target: &PathBases,
+    ) -> Result<serde_json::Value> {
+        let mut layers = self.file_layers(options, false)?;
+        for layer in &mut layers {
+            if let Some(bases) = layer.source.path_bases() {
+                rebase_relative_paths(&mut layer.value, &bases, target);
+            }
+        }
+        Ok(merge_layers(&layers).value)
+    }
+
+    /// Layers 2-6 of `load_layers`, with placeholders and MCP server references
+    /// expanded when `expand` is set
+    fn file_layers(&self, options: &ConfigLoadOptions, expand: bool) -> Result<Vec<ConfigLayer>> {
+        let mut layers = Vec::new();
+
+        // Global and local configs are optional
+        let directories = [
+            (self.global_dir.as_ref(), "global"),
+            (self.local_dir.as_ref(), "local"),
+        ];
+        for (directory, kind) in directories {
+            let Some(directory) = directory.filter(|d| d.has_config()) else {
+                continue;
+            };
             info!(
-                "Loading configuration from explicit file: {}",
-                config_file.display()
+                "Loading {} configuration: {}",
+                kind,
+                directory.config_file.display()
             );
-            let config = self.load_config_file(config_file).with_context(|| {
-                format!("Failed to load config file: {}", config_file.display())
-            })?;
-            return Ok((config, ConfigSource::File(config_file.clone())));
+            // An existing but invalid config is an error rather than a silent fallback
+            let value = self
+                .read_config_value(&directory.config_file, expand)
+                .with_context(|| format!("Failed to load {} config", kind))?;
+            let source = if kind == "global" {
+                ConfigSource::Global(directory.clone())
+            } else {
+                ConfigSource::Local(directory.clone())
+            };
+            layers.push(ConfigLayer::new(source, value));
         }
 
         if let Some(preset_name) = &options.preset {
             info!("Loading configuration from preset: {}", preset_name);
-            return self.load_preset(preset_name);
+            self.collect_preset_layers(preset_name, None, &mut Vec::new(), &mut layers, expand)?;
         }
 
-        // Try local config
-        if let Some(ref local_dir) = self.local_dir {
-            if local_dir.has_config() {
-                info!(
-                    "Loading local configuration: {}",
-                    local_dir.config_file.display()
-                );
-                match self.load_config_file(&local_dir.config_file) {
-                    Ok(config) => return Ok((config, ConfigSource::Local(local_dir.clone()))),
-                    Err(e) => {
-                        warn!("Failed to load local config, falling back: {}", e);
-                    }
-                }
-            }
+        if let Some(config_file) = &options.config_file {
+            info!(
+                "Loading configuration from explicit file: {}",
+                config_file.display()
+            );
+            let value = self
+                .read_config_value(config_file, expand)
+                .with_context(|| {
+                    format!("Failed to load config file: {}", config_file.display())
+                })?;
+            layers.push(ConfigLayer::new(
+                ConfigSource::File(config_file.clone()),
+                value,
+            ));
         }
 
-        // Try global config
-        if let Some(ref global_dir) = self.global_dir {
-            if global_dir.has_config() {
-                info!(
-                    "Loading global configuration: {}",
-                    global_dir.config_file.display()
-                );
-                match self.load_config_file(&global_dir.config_file) {
-                    Ok(config) => return Ok((config, ConfigSource::Global(global_dir.clone()))),
-                    Err(e) => {
-                        warn!("Failed to load global config, falling back: {}", e);
-                    }
-                }
-            }
+        if !options.overrides.is_empty() {
+            layers.push(ConfigLayer::new(
+                ConfigSource::CommandLine,
+                options.overrides.to_value(),
+            ));
         }
 
-        // the current working directory
-        let dir = std::env::current_dir()
-            .map_err(|e| anyhow::anyhow!("Failed to get current directory: {}", e))?
-            .to_string_lossy()
-            .to_string();
+        Ok(layers)
+    }
 
-        // Fall back to defaults
-        info!("Using built-in default configuration");
-        Ok((ConversationConfig::default(dir), ConfigSource::Default))
+    /// Config and preset files ignored because a file of the same name exists in a
+    /// format that wins, with the file used instead
+    pub fn shadowed_config_files(&self) -> Vec<(PathBuf, PathBuf)> {
+        [self.global_dir.as_ref(), self.local_dir.as_ref()]
+            .into_iter()
+            .flatten()
+            .flat_map(ThChatDirectory::shadowed_config_files)
+            .collect()
     }
 
-    /// Load a named preset
-    pub fn load_preset(&self, preset_name: &str) -> Result<(ConversationConfig, ConfigSource)> {
-        // Try local presets first, then global
-        if let Some(ref local_dir) = self.local_dir {
-            let preset_file = local_dir.preset_file(preset_name);
-            if preset_file.exists() {
-                debug!("Loading local preset: {}", preset_file.display());
-                let config = self
-                    .load_config_file(&preset_file)
-                    .with_context(|| format!("Failed to load local preset '{}'", preset_name))?;
-                return Ok((
-                    config,
-                    ConfigSource::Preset {
-                        directory: local_dir.clone(),
-                        name: preset_name.to_string(),
-                    },
-                ));
+    /// Config and preset files in the local and global directories
+    pub fn config_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for directory in [self.global_dir.as_ref(), self.local_dir.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            if directory.has_config() {
+                files.push(directory.config_file.clone());
+            }
+            if let Ok(presets) = directory.list_presets() {
+                files.extend(presets.iter().map(|name| directory.preset_file(name)));
             }
         }
+        files
+    }
 
-        if let Some(ref global_dir) = self.global_dir {
-            let preset_file = global_dir.preset_file(preset_name);
+    /// Load a named preset (including the presets it extends) over the built-in
+    /// defaults, without the global and local configs
+    pub fn load_preset(&self, preset_name: &str) -> Result<LayeredConfig> {
+        let mut layers = vec![default_layer()?];
+        layers.extend(self.preset_layers(preset_name)?);
+        let merged = merge_layers(&layers);
+        let config: ConversationConfig = serde_json::from_value(merged.value.clone())
+            .with_context(|| format!("Failed to load preset '{}'", preset_name))?;
+
+        Ok(LayeredConfig {
+            config,
+            layers,
+            merged,
+        })
+    }
+
+    /// Load a preset and the presets it `extends`, as layers ordered parents first
+    pub fn preset_layers(&self, preset_name: &str) -> Result<Vec<ConfigLayer>> {
+        let mut layers = Vec::new();
+        self.collect_preset_layers(preset_name, None, &mut Vec::new(), &mut layers, true)?;
+        Ok(layers)
+    }
+
+    fn collect_preset_layers(
+        &self,
+        preset_name: &str,
+        skip_file: Option<&Path>,
+        chain: &mut Vec<(String, PathBuf)>,
+        layers: &mut Vec<ConfigLayer>,
+        expand: bool,
+    ) -> Result<()> {
+        let (path, source) = self.find_preset(preset_name, skip_file)?;
+
+        if chain.iter().any(|(_, visited)| visited == &path) {
+            let cycle: Vec<&str> = chain
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain(std::iter::once(preset_name))
+                .collect();
+            anyhow::bail!("Preset inheritance cycle: {}", cycle.join(" -> "));
+        }
+
+        let mut value = self
+            .read_config_value(&path, expand)
+            .with_context(|| format!("Failed to load preset '{}'", preset_name))?;
+        let parents = take_extends(&mut value)
+            .with_context(|| format!("Invalid 'extends' in preset '{}'", preset_name))?;
+
+        chain.push((preset_name.to_string(), path.clone()));
+        for parent in parents {
+            // A preset extending its own name refers to the next directory
+            // (e.g. a local "base" extending the global "base")
+            let skip = (parent == preset_name).then_some(path.as_path());
+            debug!("Preset '{}' extends '{}'", preset_name, parent);
+            self.collect_preset_layers(&parent, skip, chain, layers, expand)?;
+        }
+        chain.pop();
+
+        layers.push(ConfigLayer::new(source, value));
+        Ok(())
+    }
+
+    /// Locate a named preset, preferring local presets over global ones. When
+    /// `skip_file` is given, only directories after the one holding it are searched.
+    fn find_preset(
+        &self,
+        preset_name: &str,
+        skip_file: Option<&Path>,
+    ) -> Result<(PathBuf, ConfigSource)> {
+        let mut skipping = skip_file.is_some();
+        for directory in [self.local_dir.as_ref(), self.global_dir.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            let preset_file = directory.preset_file(preset_name);
+            if skipping {
+                skipping = skip_file != Some(preset_file.as_path());
+                continue;
+            }
             if preset_file.exists() {
-                debug!("Loading global preset: {}", preset_file.display());
-                let config = self
-                    .load_config_file(&preset_file)
-                    .with_context(|| format!("Failed to load global preset '{}'", preset_name))?;
+                debug!("Found preset: {}", preset_file.display());
                 return Ok((
-                    config,
+                    preset_file,
                     ConfigSource::Preset {
-                        directory: global_dir.clone(),
+                        directory: directory.clone(),
                         name: preset_name.to_string(),
                     },
                 ));
@@ -253,14 +868,102 @@ impl ConfigManager {
 
     /// Load configuration from a specific file
     fn load_config_file(&self, path: &Path) -> Result<ConversationConfig> {
+        let value = self.load_config_value(path)?;
+        let config: ConversationConfig = serde_json::from_value(value)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        debug!("Successfully loaded configuration from: {}", path.display());
+        Ok(config)
+    }
+
+    /// Read a (possibly partial) JSON, TOML or YAML config document and expand its
+    /// placeholders
+    fn load_config_value(&self, path: &Path) -> Result<serde_json::Value> {
+        self.read_config_value(path, true)
+    }
+
+    /// Read and validate a config document, expanding placeholders and MCP server
+    /// references only when `expand` is set
+    fn read_config_value(&self, path: &Path, expand: bool) -> Result<serde_json::Value> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let format = ConfigFormat::from_path(path);
 
-        let config: ConversationConfig = serde_json::from_str(&content)
+        let issues = validate_config_text(&content, format);
+        if !issues.is_empty() {
+            let details: Vec<String> = issues
+                .iter()
+                .map(|issue| format!("  {}:{}", path.display(), issue))
+                .collect();
+            anyhow::bail!(
+                "Invalid config file {} (run 'th-chat config validate' for details):\n{}",
+                path.display(),
+                details.join("\n")
+            );
+        }
+
+        let mut value = format
+            .parse(&content)
             .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        if !expand {
+            return Ok(value);
+        }
 
-        debug!("Successfully loaded configuration from: {}", path.display());
-        Ok(config)
+        // Expand ${VAR}, ${VAR:-default} and ${file:path} placeholders
+        interpolate_json(&mut value, path.parent())
+            .with_context(|| format!("Failed to interpolate config file: {}", path.display()))?;
+
+        // Replace MCP server names with their definitions from mcp/
+        expand_mcp_references(&mut value, |name| self.mcp_server_value(name))
+            .with_context(|| format!("Failed to resolve MCP servers in: {}", path.display()))?;
+
+        Ok(value)
+    }
+
+    /// Named MCP server definitions from the local and global `mcp/` directories,
+    /// local definitions hiding global ones with the same name
+    pub fn mcp_definitions(&self) -> Result<Vec<(McpDefinition, PathBuf)>> {
+        let mut definitions: Vec<(McpDefinition, PathBuf)> = Vec::new();
+        for directory in [self.local_dir.as_ref(), self.global_dir.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            for definition in list_definitions(&directory.mcp_dir)? {
+                if definitions.iter().all(|(d, _)| d.name != definition.name) {
+                    let path = definition_path(&directory.mcp_dir, &definition.name)?;
+                    definitions.push((definition, path));
+                }
+            }
+        }
+        definitions.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        Ok(definitions)
+    }
+
+    /// Locate a named MCP server definition, preferring the local directory
+    pub fn find_mcp_definition(&self, name: &str) -> Result<(McpDefinition, PathBuf)> {
+        for directory in [self.local_dir.as_ref(), self.global_dir.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            let path = definition_path(&directory.mcp_dir, name)?;
+            if path.exists() {
+                return Ok((load_definition(&path)?, path));
+            }
+        }
+        anyhow::bail!(
+            "MCP server '{}' not found (add it with 'th-chat mcp add {} <command>')",
+            name,
+            name
+        );
+    }
+
+    /// The `mcp_servers` entry for a named definition, with placeholders expanded
+    fn mcp_server_value(&self, name: &str) -> Result<serde_json::Value> {
+        let (definition, path) = self.find_mcp_definition(name)?;
+        let mut value = definition.to_server_value();
+        interpolate_json(&mut value, path.parent())
+            .with_context(|| format!("Failed to interpolate {}", path.display()))?;
+        Ok(value)
     }
 
     /// List available presets
@@ -300,14 +1003,114 @@ impl ConfigManager {
         Ok(presets)
     }
 
+    /// Resolve the chat-state actor manifest. A command line (or environment) override
+    /// wins and is resolved against the current directory; otherwise the configured
+    /// manifest is resolved against the directory of the layer that set it. Without a
+    /// loaded configuration the default manifest is used.
+    pub fn resolve_actor_manifest(
+        &self,
+        cli_override: Option<&str>,
+        layered: Option<&LayeredConfig>,
+    ) -> Result<String> {
+        let registry_dir = default_actor_registry_dir();
+
+        if let Some(reference) = cli_override {
+            let cwd = std::env::current_dir().context("Failed to get current directory")?;
+            return resolve_actor_manifest(reference, &cwd, registry_dir.as_deref());
+        }
+
+        let reference = layered
+            .and_then(|layered| layered.config.actor_manifest.as_deref())
+            .unwrap_or(DEFAULT_ACTOR_MANIFEST);
+        let base_dir = match layered {
+            Some(layered) => self.field_base_dir(layered, "actor_manifest")?,
+            None => self.default_base_dir()?,
+        };
+        resolve_actor_manifest(reference, &base_dir, registry_dir.as_deref())
+    }
+
+    /// Directory that relative paths in a config field resolve against: the directory
+    /// of the layer that set it, or the default for built-in values
+    pub fn field_base_dir(&self, layered: &LayeredConfig, field: &str) -> Result<PathBuf> {
+        match layered.base_dir_for(field) {
+            Some(dir) => Ok(dir),
+            None => self.default_base_dir(),
+        }
+    }
+
+    /// The .th-chat directory, or the current directory when there is none
+    fn default_base_dir(&self) -> Result<PathBuf> {
+        match self.get_sessions_directory() {
+            Some(dir) => Ok(dir.root.clone()),
+            None => std::env::current_dir().context("Failed to get current directory"),
+        }
+    }
+
+    /// Directory targeted by commands that edit configuration
+    pub fn target_directory(&self, scope: ConfigScope) -> Result<&ThChatDirectory> {
+        match scope {
+            ConfigScope::Local => self
+                .local_dir
+                .as_ref()
+                .context("No local .th-chat directory found (run 'th-chat init' to create one)"),
+            ConfigScope::Global => self.global_dir.as_ref().context(
+                "No global .th-chat directory found (run 'th-chat init --global' to create one)",
+            ),
+            ConfigScope::Auto => self
+                .local_dir
+                .as_ref()
+                .or(self.global_dir.as_ref())
+                .context("No .th-chat directory found (run 'th-chat init' to create one)"),
+        }
+    }
+
+    /// Preset file targeted by commands that edit presets. Without an explicit
+    /// scope an existing preset is edited where it lives.
+    pub fn preset_target(&self, preset_name: &str, scope: ConfigScope) -> Result<PathBuf> {
+        if scope == ConfigScope::Auto {
+            if let Ok((path, _)) = self.find_preset(preset_name, None) {
+                return Ok(path);
+            }
+        }
+        Ok(self.target_directory(scope)?.preset_file(preset_name))
+    }
+
+    /// Locate an existing preset file: the one that would be used, or the one in
+    /// the chosen directory
+    pub fn locate_preset(&self, preset_name: &str, scope: ConfigScope) -> Result<PathBuf> {
+        if scope == ConfigScope::Auto {
+            return Ok(self.find_preset(preset_name, None)?.0);
+        }
+        let directory = self.target_directory(scope)?;
+        let path = directory.preset_file(preset_name);
+        if !path.exists() {
+            anyhow::bail!(
+                "Preset '{}' not found in {}",
+                preset_name,
+                directory.presets_dir.display()
+            );
+        }
+        Ok(path)
+    }
+
+    /// Path bases of a preset file returned by `locate_preset`
+    pub fn preset_path_bases(&self, preset_file: &Path) -> PathBases {
+        [self.local_dir.as_ref(), self.global_dir.as_ref()]
+            .into_iter()
+            .flatten()
+            .find(|directory| preset_file.parent() == Some(directory.presets_dir.as_path()))
+            .map(PathBases::for_preset)
+            .unwrap_or_else(|| PathBases::for_file(preset_file))
+    }
+
     /// Get the appropriate directory for sessions
     pub fn get_sessions_directory(&self) -> Option<&ThChatDirectory> {
         // Prefer local, fall back to global
         self.local_dir.as_ref().or(self.global_dir.as_ref())
     }
 
-    /// Create default configuration files
-    pub fn create_default_config(&self, directory: &ThChatDirectory) -> Result<()> {
+    /// Create default configuration files, returning the example presets written
+    pub fn create_default_config(&self, directory: &ThChatDirectory) -> Result<Vec<String>> {
         info!(
             "Creating default configuration in: {}",
             directory.root.display()
@@ -319,8 +1122,17 @@ impl ConfigManager {
             .to_string_lossy()
             .to_string();
 
-        // Create main config.json
-        let default_config = ConversationConfig::default(dir);
+        // Create main config.json, leaving out MCP servers that are not installed
+        let installed = detect_installed_mcp_servers();
+        let mut default_config = ConversationConfig::default(dir);
+        default_config.mcp_servers.retain(|server| {
+            let command = server.config.command.as_str();
+            let keep = installed.iter().any(|s| s.command == command);
+            if !keep {
+                info!("Leaving out MCP server '{}': not installed", command);
+            }
+            keep
+        });
         let config_json = serde_json::to_string_pretty(&default_config)?;
         fs::write(&directory.config_file, config_json).with_context(|| {
             format!(
@@ -330,95 +1142,194 @@ impl ConfigManager {
         })?;
 
         // Create example presets
-        self.create_example_presets(directory)?;
+        let presets = self.create_example_presets(directory, &installed)?;
 
         info!("Created default configuration successfully");
-        Ok(())
+        Ok(presets)
     }
 
-    /// Create example preset files
-    fn create_example_presets(&self, directory: &ThChatDirectory) -> Result<()> {
-        let project_dir = std::env::current_dir()
-            .map_err(|e| anyhow::anyhow!("Failed to get current directory: {}", e))?
-            .to_string_lossy()
-            .to_string();
+    /// Create example preset files for the MCP servers installed on this machine.
+    /// Returns the names of the presets that were written.
+    fn create_example_presets(
+        &self,
+        directory: &ThChatDirectory,
+        installed: &[KnownMcpServer],
+    ) -> Result<Vec<String>> {
+        let is_installed = |command: &str| installed.iter().any(|s| s.command == command);
+
         // Coding preset
         let coding_preset = ConversationConfig {
             model_config: ModelConfig {
                 model: "claude-sonnet-4-20250514".to_string(),
                 provider: "anthropic".to_string(),
+                context_window: None,
             },
             temperature: Some(1.0),
             max_tokens: 8192,
             system_prompt: Some("You are pair programming with another developer. You both have access to the filesystem. Make sure you and your pair programmer come to a consensus on the best path forward before committing any changes to the project".to_string()),
+            system_prompt_file: None,
             title: "Sonnet 4 Session".to_string(),
             mcp_servers: vec![
                 McpServer {
+                    name: None,
                     actor_id: None,
                     config: McpConfig {
-                        command: "/Users/colinrozzi/work/mcp-servers/bin/fs-mcp-server".to_string(),
-                        args: vec!["--allowed-dirs".to_string(), project_dir.clone()],
+                        command: "fs-mcp-server".to_string(),
+                        args: vec!["--allowed-dirs".to_string(), "${PWD:-.}".to_string()],
                     },
                     tools: None,
+                    allowed_tools: None,
+                    denied_tools: Vec::new(),
                 }
             ],
+            model_pricing: default_model_pricing(),
+            retry: RetryPolicy::default(),
+            actor_manifest: None,
+            allowed_tools: None,
+            denied_tools: Vec::new(),
+            confirm_tools: vec!["write_*".to_string(), "*delete*".to_string(), "*shell*".to_string()],
+            tool_renderers: HashMap::new(),
         };
 
-        let coding_json = serde_json::to_string_pretty(&coding_preset)?;
-        fs::write(directory.preset_file("sonnet-4"), coding_json)?;
-
         // Research preset
         let research_preset = ConversationConfig {
             model_config: ModelConfig {
                 model: "gemini-2.5-flash-preview-04-17".to_string(),
                 provider: "google".to_string(),
+                context_window: None,
             },
             temperature: Some(1.0),
             max_tokens: 65535,
             system_prompt: Some("You are pair programming with another developer. You both have access to the filesystem. Make sure you and your pair programmer come to a consensus on the best path forward before committing any changes to the project".to_string()),
+            system_prompt_file: None,
             title: "Gemini 2.5 Flash Session".to_string(),
             mcp_servers: vec![
                 McpServer {
+                    name: None,
                     actor_id: None,
                     config: McpConfig {
-                        command: "/Users/colinrozzi/work/mcp-servers/simple-fs-mcp/target/release/simple-fs-mcp-server".to_string(),
-                        args: vec!["--allowed-dirs".to_string(), project_dir.clone()],
+                        command: "simple-fs-mcp-server".to_string(),
+                        args: vec!["--allowed-dirs".to_string(), "${PWD:-.}".to_string()],
                     },
                     tools: None,
+                    allowed_tools: None,
+                    denied_tools: Vec::new(),
                 }
             ],
+            model_pricing: default_model_pricing(),
+            retry: RetryPolicy::default(),
+            actor_manifest: None,
+            allowed_tools: None,
+            denied_tools: Vec::new(),
+            confirm_tools: Vec::new(),
+            tool_renderers: HashMap::new(),
         };
 
-        let research_json = serde_json::to_string_pretty(&research_preset)?;
-        fs::write(directory.preset_file("gemini-2.5-flash"), research_json)?;
-
         // Research preset
         let gemini_pro_preset = ConversationConfig {
             model_config: ModelConfig {
                 model: "gemini-2.5-pro-preview-06-05".to_string(),
                 provider: "google".to_string(),
+                context_window: None,
             },
             temperature: Some(1.0),
             max_tokens: 65535,
             system_prompt: Some("You are pair programming with another developer. You both have access to the filesystem. Make sure you and your pair programmer come to a consensus on the best path forward before committing any changes to the project".to_string()),
+            system_prompt_file: None,
             title: "Gemini 2.5 Pro Session".to_string(),
             mcp_servers: vec![
                 McpServer {
+                    name: None,
                     actor_id: None,
                     config: McpConfig {
-                        command: "/Users/colinrozzi/work/mcp-servers/bin/fs-mcp-server".to_string(),
-                        args: vec!["--allowed-dirs".to_string(), project_dir.clone()],
+                        command: "fs-mcp-server".to_string(),
+                        args: vec!["--allowed-dirs".to_string(), "${PWD:-.}".to_string()],
                     },
                     tools: None,
+                    allowed_tools: None,
+                    denied_tools: Vec::new(),
                 }
             ],
+            model_pricing: default_model_pricing(),
+            retry: RetryPolicy::default(),
+            actor_manifest: None,
+            allowed_tools: None,
+            denied_tools: Vec::new(),
+            confirm_tools: Vec::new(),
+            tool_renderers: HashMap::new(),
         };
 
-        let gemini_pro_json = serde_json::to_string_pretty(&gemini_pro_preset)?;
-        fs::write(directory.preset_file("gemini-2.5-pro"), gemini_pro_json)?;
+        let presets = [
+            ("sonnet-4", coding_preset),
+            ("gemini-2.5-flash", research_preset),
+            ("gemini-2.5-pro", gemini_pro_preset),
+        ];
+        let mut base = serde_json::Map::new();
+        let mut written = Vec::new();
+        for (name, preset) in presets {
+            // Only write presets whose MCP servers are installed
+            if let Some(missing) = preset
+                .mcp_servers
+                .iter()
+                .find(|server| !is_installed(&server.config.command))
+            {
+                info!(
+                    "Skipping preset '{}': MCP server '{}' is not installed",
+                    name, missing.config.command
+                );
+                continue;
+            }
 
-        debug!("Created example presets: sonnet-4, gemini-2.5-flash");
-        Ok(())
+            // Shared fields move to the base preset, which the others extend
+            let mut value = serde_json::to_value(&preset)?;
+            if let Some(map) = value.as_object_mut() {
+                for field in SHARED_PRESET_FIELDS {
+                    if let Some(shared) = map.remove(*field) {
+                        base.insert(field.to_string(), shared);
+                    }
+                }
+                map.insert("extends".to_string(), BASE_PRESET.into());
+            }
+
+            let json = serde_json::to_string_pretty(&value)?;
+            fs::write(directory.preset_file(name), json)?;
+            written.push(name.to_string());
+        }
+
+        if !written.is_empty() {
+            let json = serde_json::to_string_pretty(&base)?;
+            fs::write(directory.preset_file(BASE_PRESET), json)?;
+            written.insert(0, BASE_PRESET.to_string());
+        }
+
+        debug!("Created example presets: {:?}", written);
+        Ok(written)
+    }
+}
+
+/// Preset holding the fields shared by the example presets
+const BASE_PRESET: &str = "base";
+
+/// Example preset fields written once to the base preset
+const SHARED_PRESET_FIELDS: &[&str] = &["system_prompt", "temperature", "model_pricing", "retry"];
+
+/// Remove the `extends` key from a preset document, returning the parent preset
+/// names in merge order. Accepts a single name or a list of names.
+fn take_extends(value: &mut serde_json::Value) -> Result<Vec<String>> {
+    let Some(extends) = value.as_object_mut().and_then(|map| map.remove("extends")) else {
+        return Ok(Vec::new());
+    };
+
+    match extends {
+        serde_json::Value::String(name) => Ok(vec![name]),
+        serde_json::Value::Array(names) => names
+            .into_iter()
+            .map(|name| match name {
+                serde_json::Value::String(name) => Ok(name),
+                other => anyhow::bail!("expected a preset name, found {}", other),
+            })
+            .collect(),
+        other => anyhow::bail!("expected a preset name or list of names, found {}", other),
     }
 }
 
@@ -430,6 +1341,9 @@ pub struct ConfigLoadOptions {
 
     /// Named preset to load
     pub preset: Option<String>,
+
+    /// Command line overrides, applied last
+    pub overrides: ConfigOverrides,
 }
 
 #[cfg(test)]
@@ -454,4 +1368,241 @@ mod tests {
         assert_eq!(config.model_config.provider, "google");
         assert!(matches!(source, ConfigSource::Default));
     }
+
+    fn write_preset(directory: &ThChatDirectory, name: &str, value: serde_json::Value) {
+        fs::create_dir_all(&directory.presets_dir).unwrap();
+        fs::write(directory.preset_file(name), value.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_preset_extends() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = ThChatDirectory::new(temp_dir.path().join("local"));
+        let global = ThChatDirectory::new(temp_dir.path().join("global"));
+
+        write_preset(
+            &global,
+            "base",
+            serde_json::json!({
+                "model_config": { "model": "base-model", "provider": "google" },
+                "max_tokens": 1000,
+                "system_prompt": "shared prompt",
+                "title": "Base",
+                "mcp_servers": []
+            }),
+        );
+        // A local preset extending its own name resolves to the global one
+        write_preset(
+            &local,
+            "base",
+            serde_json::json!({ "extends": "base", "max_tokens": 2000 }),
+        );
+        write_preset(
+            &local,
+            "child",
+            serde_json::json!({ "extends": ["base"], "title": "Child" }),
+        );
+
+        let manager = ConfigManager {
+            local_dir: Some(local.clone()),
+            global_dir: Some(global),
+        };
+
+        let config = manager.load_preset("child").unwrap().config;
+        assert_eq!(config.title, "Child");
+        assert_eq!(config.max_tokens, 2000);
+        assert_eq!(config.system_prompt.as_deref(), Some("shared prompt"));
+        assert_eq!(config.model_config.model, "base-model");
+
+        // Partial presets fill the remaining fields from the defaults
+        write_preset(&local, "partial", serde_json::json!({ "max_tokens": 10 }));
+        let config = manager.load_preset("partial").unwrap().config;
+        assert_eq!(config.max_tokens, 10);
+        assert_eq!(config.title, "CLI Chat");
+
+        // Cycles are reported instead of recursing forever
+        write_preset(&local, "a", serde_json::json!({ "extends": "b" }));
+        write_preset(&local, "b", serde_json::json!({ "extends": "a" }));
+        let err = manager.load_preset("a").unwrap_err();
+        assert!(err.to_string().contains("a -> b -> a"));
+    }
+
+    #[test]
+    fn test_preset_mcp_server_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = ThChatDirectory::new(temp_dir.path().join("local"));
+        let global = ThChatDirectory::new(temp_dir.path().join("global"));
+
+        let definition = |command: &str| McpDefinition {
+            name: "fs".to_string(),
+            description: None,
+            command: command.to_string(),
+            args: vec!["${TH_CHAT_TEST_UNSET_VAR:-here}".to_string()],
+        };
+        crate::mcp_registry::save_definition(&global.mcp_dir, &definition("global-fs")).unwrap();
+        crate::mcp_registry::save_definition(&local.mcp_dir, &definition("local-fs")).unwrap();
+        write_preset(
+            &local,
+            "tools",
+            serde_json::json!({ "mcp_servers": ["fs"] }),
+        );
+        write_preset(
+            &local,
+            "broken",
+            serde_json::json!({ "mcp_servers": ["missing"] }),
+        );
+
+        let manager = ConfigManager {
+            local_dir: Some(local),
+            global_dir: Some(global),
+        };
+
+        assert_eq!(manager.mcp_definitions().unwrap().len(), 1);
+        let layers = manager.preset_layers("tools").unwrap();
+        let server = &layers[0].value["mcp_servers"][0];
+        assert_eq!(server["name"], "fs");
+        assert_eq!(server["config"]["command"], "local-fs");
+        assert_eq!(server["config"]["args"], serde_json::json!(["here"]));
+
+        // Snapshots keep references as written and leave out the defaults
+        let options = ConfigLoadOptions {
+            preset: Some("tools".to_string()),
+            ..Default::default()
+        };
+        let target = PathBases::for_preset(manager.local_dir.as_ref().unwrap());
+        assert_eq!(
+            manager.load_unexpanded(&options, &target).unwrap(),
+            serde_json::json!({ "mcp_servers": ["fs"] })
+        );
+
+        let err = manager.preset_layers("broken").unwrap_err();
+        assert!(format!("{:#}", err).contains("MCP server 'missing' not found"));
+    }
+
+    #[test]
+    fn test_rebase_relative_paths() {
+        let global = ThChatDirectory::new(PathBuf::from("/home/user/.th-chat"));
+        let local = ThChatDirectory::new(PathBuf::from("/work/project/.th-chat"));
+        let mut value = serde_json::json!({
+            "system_prompt_file": "prompts/review.md",
+            "actor_manifest": "registry:chat-state",
+            "mcp_servers": [{ "config": { "env": { "TOKEN": "${file:token}" } } }]
+        });
+
+        let from = PathBases::for_preset(&global);
+        assert!(rebase_relative_paths(&mut value.clone(), &from, &from).is_empty());
+
+        let rewritten = rebase_relative_paths(&mut value, &from, &PathBases::for_preset(&local));
+        assert_eq!(
+            rewritten,
+            vec!["system_prompt_file", "mcp_servers[0].config.env.TOKEN"]
+        );
+        assert_eq!(
+            value["system_prompt_file"],
+            "/home/user/.th-chat/prompts/review.md"
+        );
+        assert_eq!(value["actor_manifest"], "registry:chat-state");
+        assert_eq!(
+            value["mcp_servers"][0]["config"]["env"]["TOKEN"],
+            "${file:/home/user/.th-chat/presets/token}"
+        );
+    }
+
+    #[test]
+    fn test_resolve_actor_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = temp_dir.path().join("registry");
+        fs::create_dir_all(registry.join("chat-state")).unwrap();
+        fs::write(registry.join("chat-state").join("manifest.toml"), "").unwrap();
+        fs::write(temp_dir.path().join("local.toml"), "").unwrap();
+
+        // Registry references
+        let resolved =
+            resolve_actor_manifest("registry:chat-state", temp_dir.path(), Some(&registry))
+                .unwrap();
+        assert!(resolved.ends_with("manifest.toml"));
+        assert!(
+            resolve_actor_manifest("registry:missing", temp_dir.path(), Some(&registry)).is_err()
+        );
+        assert!(resolve_actor_manifest("registry:chat-state", temp_dir.path(), None).is_err());
+
+        // Paths relative to the base directory
+        let resolved = resolve_actor_manifest("local.toml", temp_dir.path(), None).unwrap();
+        assert_eq!(
+            resolved,
+            temp_dir.path().join("local.toml").to_string_lossy()
+        );
+        assert!(resolve_actor_manifest("nope.toml", temp_dir.path(), None).is_err());
+
+        // URLs pass through untouched
+        let url = "https://example.com/chat-state/manifest.toml";
+        assert_eq!(
+            resolve_actor_manifest(url, temp_dir.path(), None).unwrap(),
+            url
+        );
+    }
+
+    #[test]
+    fn test_mcp_command_resolution() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = temp_dir.path().join("example-mcp-server");
+        fs::write(&binary, "").unwrap();
+
+        // Explicit paths are checked directly
+        assert_eq!(
+            find_executable(&binary.to_string_lossy()),
+            Some(binary.clone())
+        );
+        assert!(find_executable(&temp_dir.path().join("missing").to_string_lossy()).is_none());
+        assert!(find_executable("th-chat-definitely-not-installed").is_none());
+
+        let config = McpConfig {
+            command: binary.to_string_lossy().to_string(),
+            args: vec!["--flag".to_string()],
+        };
+        let resolved = config.resolve().unwrap();
+        assert_eq!(resolved.command, binary.to_string_lossy());
+        assert_eq!(resolved.args, vec!["--flag".to_string()]);
+
+        let missing = McpConfig {
+            command: "th-chat-definitely-not-installed".to_string(),
+            args: vec![],
+        };
+        assert!(missing.resolve().is_err());
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 5_000,
+            backoff_multiplier: 2.0,
+            retryable_codes: vec!["429".to_string()],
+        };
+
+        assert!(policy.is_retryable("429"));
+        assert!(!policy.is_retryable("400"));
+        assert_eq!(policy.backoff_for(2).as_millis(), 1_000);
+        assert_eq!(policy.backoff_for(3).as_millis(), 2_000);
+        assert_eq!(policy.backoff_for(4).as_millis(), 4_000);
+        assert_eq!(policy.backoff_for(5).as_millis(), 5_000);
+    }
+
+    #[test]
+    fn test_effective_context_window() {
+        let mut model_config = ModelConfig {
+            model: "claude-sonnet-4-20250514".to_string(),
+            provider: "anthropic".to_string(),
+            context_window: None,
+        };
+        assert_eq!(model_config.effective_context_window(), Some(200_000));
+
+        model_config.context_window = Some(32_000);
+        assert_eq!(model_config.effective_context_window(), Some(32_000));
+
+        model_config.model = "some-local-model".to_string();
+        model_config.context_window = None;
+        assert_eq!(model_config.effective_context_window(), None);
+    }
 }
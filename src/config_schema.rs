@@ -0,0 +1,576 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::config_format::ConfigFormat;
+
+/// Names of the built-in tool renderers accepted in `tool_renderers`
+pub const RENDERER_NAMES: &[&str] = &["json", "text", "table", "diff", "tree"];
+
+/// JSON Schema describing a config or preset file.
+///
+/// Files are layers that may set only some fields, so top-level fields are not
+/// required here; completeness is checked after the layers are merged.
+pub fn config_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "th-chat configuration",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "$schema": { "type": "string" },
+            "extends": {
+                "description": "Preset(s) this preset inherits from",
+                "type": ["string", "array"],
+                "items": { "type": "string", "minLength": 1 }
+            },
+            "model_config": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "model": { "type": "string", "minLength": 1 },
+                    "provider": { "type": "string", "minLength": 1 },
+                    "context_window": { "type": ["integer", "null"], "minimum": 1 }
+                }
+            },
+            "temperature": { "type": ["number", "null"], "minimum": 0.0, "maximum": 2.0 },
+            "max_tokens": { "type": "integer", "minimum": 1 },
+            "system_prompt": { "type": ["string", "null"] },
+            "system_prompt_file": { "type": ["string", "null"], "minLength": 1 },
+            "title": { "type": "string" },
+            "mcp_servers": {
+                "type": "array",
+                "items": {
+                    "description": "An MCP server, or the name of a definition in mcp/",
+                    "type": ["object", "string"],
+                    "minLength": 1,
+                    "additionalProperties": false,
+                    "required": ["config"],
+                    "properties": {
+                        "name": { "type": ["string", "null"], "minLength": 1 },
+                        "actor_id": { "type": ["string", "null"] },
+                        "config": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["command"],
+                            "properties": {
+                                "command": { "type": "string", "minLength": 1 },
+                                "args": { "type": "array", "items": { "type": "string" } }
+                            }
+                        },
+                        "tools": { "type": ["array", "null"] },
+                        "allowed_tools": {
+                            "type": ["array", "null"],
+                            "items": { "type": "string", "minLength": 1 }
+                        },
+                        "denied_tools": {
+                            "type": "array",
+                            "items": { "type": "string", "minLength": 1 }
+                        }
+                    }
+                }
+            },
+            "model_pricing": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["input_per_million", "output_per_million"],
+                    "properties": {
+                        "input_per_million": { "type": "number", "minimum": 0.0 },
+                        "output_per_million": { "type": "number", "minimum": 0.0 }
+                    }
+                }
+            },
+            "retry": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "max_attempts": { "type": "integer", "minimum": 1 },
+                    "initial_backoff_ms": { "type": "integer", "minimum": 0 },
+                    "max_backoff_ms": { "type": "integer", "minimum": 0 },
+                    "backoff_multiplier": { "type": "number", "minimum": 1.0 },
+                    "retryable_codes": { "type": "array", "items": { "type": "string" } }
+                }
+            },
+            "actor_manifest": { "type": ["string", "null"], "minLength": 1 },
+            "allowed_tools": {
+                "description": "Globs of tool names the session may use",
+                "type": ["array", "null"],
+                "items": { "type": "string", "minLength": 1 }
+            },
+            "denied_tools": {
+                "description": "Globs of tool names the session may not use",
+                "type": "array",
+                "items": { "type": "string", "minLength": 1 }
+            },
+            "confirm_tools": {
+                "description": "Globs of tool names that need approval before they run",
+                "type": "array",
+                "items": { "type": "string", "minLength": 1 }
+            },
+            "tool_renderers": {
+                "description": "Renderer for tools by name or glob: json, text, table, diff or tree",
+                "type": "object",
+                "additionalProperties": { "type": "string", "enum": RENDERER_NAMES }
+            }
+        }
+    })
+}
+
+/// A single problem found while validating a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// Field path, e.g. `mcp_servers[0].config.command`
+    pub path: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Validate the text of a config file, reporting syntax errors and schema
+/// violations with their line and column (field positions are located for JSON only)
+pub fn validate_config_text(text: &str, format: ConfigFormat) -> Vec<ValidationIssue> {
+    let parsed = match format {
+        ConfigFormat::Json => serde_json::from_str::<Value>(text)
+            .map_err(|e| syntax_issue(format, e.to_string(), Some((e.line(), e.column())))),
+        ConfigFormat::Toml => toml::from_str::<Value>(text).map_err(|e| {
+            let position = e.span().map(|span| line_column(text, span.start));
+            syntax_issue(format, e.message().to_string(), position)
+        }),
+        ConfigFormat::Yaml => serde_yaml::from_str::<Value>(text).map_err(|e| {
+            let position = e.location().map(|l| (l.line(), l.column()));
+            syntax_issue(format, e.to_string(), position)
+        }),
+    };
+    let value = match parsed {
+        Ok(value) => value,
+        Err(issue) => return vec![issue],
+    };
+
+    let positions = match format {
+        ConfigFormat::Json => locate_fields(text),
+        _ => HashMap::new(),
+    };
+    validate_config_value(&value)
+        .into_iter()
+        .map(|mut issue| {
+            if let Some((line, column)) = positions.get(&issue.path) {
+                issue.line = Some(*line);
+                issue.column = Some(*column);
+            }
+            issue
+        })
+        .collect()
+}
+
+fn syntax_issue(
+    format: ConfigFormat,
+    message: String,
+    position: Option<(usize, usize)>,
+) -> ValidationIssue {
+    ValidationIssue {
+        path: String::new(),
+        message: format!("invalid {}: {}", format.extension().to_uppercase(), message),
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+    }
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |last| last.chars().count())
+        + 1;
+    (line, column)
+}
+
+/// Validate a config file on disk, returning its issues
+pub fn validate_config_file(path: &std::path::Path) -> Result<Vec<ValidationIssue>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    Ok(validate_config_text(&text, ConfigFormat::from_path(path)))
+}
+
+/// Validate a parsed config document against [`config_schema`]
+pub fn validate_config_value(value: &Value) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    validate_node(value, &config_schema(), String::new(), &mut issues);
+    issues
+}
+
+/// Check `value` against the subset of JSON Schema used by [`config_schema`]
+fn validate_node(value: &Value, schema: &Value, path: String, issues: &mut Vec<ValidationIssue>) {
+    let mut issue = |message: String| {
+        issues.push(ValidationIssue {
+            path: path.clone(),
+            message,
+            line: None,
+            column: None,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.iter().any(|t| matches_type(value, t)) {
+            issue(format!(
+                "expected {}, found {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                issue(format!("must be at least {}, found {}", minimum, value));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                issue(format!("must be at most {}, found {}", maximum, value));
+            }
+        }
+    }
+
+    if let (Some(text), Some(min_length)) = (
+        value.as_str(),
+        schema.get("minLength").and_then(Value::as_u64),
+    ) {
+        if (text.chars().count() as u64) < min_length {
+            issue("must not be empty".to_string());
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let options: Vec<String> = options.iter().map(Value::to_string).collect();
+            issue(format!(
+                "must be one of {}, found {}",
+                options.join(", "),
+                value
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for field in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(field) {
+                        issue(format!("missing required field '{}'", field));
+                    }
+                }
+            }
+
+            for (key, child) in map {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => validate_node(child, child_schema, child_path, issues),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => issues.push(ValidationIssue {
+                            path: child_path,
+                            message: unknown_field_message(key, properties),
+                            line: None,
+                            column: None,
+                        }),
+                        Some(extra) if extra.is_object() => {
+                            validate_node(child, extra, child_path, issues)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_node(item, item_schema, format!("{}[{}]", path, index), issues);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn unknown_field_message(key: &str, properties: Option<&serde_json::Map<String, Value>>) -> String {
+    let suggestion = properties.and_then(|properties| {
+        properties
+            .keys()
+            .map(|known| (edit_distance(key, known), known))
+            .filter(|(distance, _)| *distance <= 2)
+            .min()
+            .map(|(_, known)| known.clone())
+    });
+    match suggestion {
+        Some(known) => format!("unknown field '{}' (did you mean '{}'?)", key, known),
+        None => format!("unknown field '{}'", key),
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Levenshtein distance, used to suggest the intended field for a typo
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Map every field path in a JSON document to the 1-based line and column where it
+/// appears (the key for object members, the value for array items)
+fn locate_fields(text: &str) -> HashMap<String, (usize, usize)> {
+    let mut locator = Locator {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+        positions: HashMap::new(),
+    };
+    locator.value(String::new());
+    locator.positions
+}
+
+struct Locator {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl Locator {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn value(&mut self, path: String) {
+        self.skip_whitespace();
+        self.positions
+            .entry(path.clone())
+            .or_insert((self.line, self.column));
+        match self.peek() {
+            Some('{') => self.object(path),
+            Some('[') => self.array(path),
+            Some('"') => {
+                self.string();
+            }
+            Some(_) => {
+                while matches!(self.peek(), Some(c) if !matches!(c, ',' | '}' | ']') && !c.is_whitespace())
+                {
+                    self.bump();
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn object(&mut self, path: String) {
+        self.bump();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('"') => {
+                    let position = (self.line, self.column);
+                    let key = self.string();
+                    let child = if path.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    self.positions.insert(child.clone(), position);
+                    self.skip_whitespace();
+                    if self.peek() == Some(':') {
+                        self.bump();
+                    }
+                    self.value(child);
+                }
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {
+                    self.bump();
+                    return;
+                }
+                Some(_) => {
+                    self.bump();
+                }
+                None => return,
+            }
+        }
+    }
+
+    fn array(&mut self, path: String) {
+        self.bump();
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(']') => {
+                    self.bump();
+                    return;
+                }
+                Some(',') => {
+                    self.bump();
+                    index += 1;
+                }
+                Some(_) => self.value(format!("{}[{}]", path, index)),
+                None => return,
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut text = String::new();
+        self.bump();
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.bump() {
+                        text.push(escaped);
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_positions() {
+        let text = r#"{
+  "model_config": { "model": "", "provider": "google" },
+  "temprature": 1.0,
+  "temperature": 3.5,
+  "tool_renderers": { "read_*": "tabel" },
+  "mcp_servers": [
+    { "config": { "args": [] } }
+  ]
+}"#;
+        let issues = validate_config_text(text, ConfigFormat::Json);
+        let find = |path: &str| issues.iter().find(|i| i.path == path).unwrap();
+
+        let empty_model = find("model_config.model");
+        assert_eq!((empty_model.line, empty_model.column), (Some(2), Some(21)));
+        assert!(empty_model.message.contains("empty"));
+
+        let typo = find("temprature");
+        assert_eq!(typo.line, Some(3));
+        assert!(typo.message.contains("did you mean 'temperature'"));
+
+        assert!(find("temperature").message.contains("at most 2"));
+        assert!(find("tool_renderers.read_*")
+            .message
+            .contains("must be one of \"json\""));
+        assert!(find("mcp_servers[0].config")
+            .message
+            .contains("missing required field 'command'"));
+    }
+
+    #[test]
+    fn test_validate_valid_and_syntax_errors() {
+        let default = crate::config_manager::ConversationConfig::default("/tmp".to_string());
+        let text = serde_json::to_string_pretty(&default).unwrap();
+        assert!(validate_config_text(&text, ConfigFormat::Json).is_empty());
+
+        let issues = validate_config_text("{\n  \"title\": \"x\",\n}", ConfigFormat::Json);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(3));
+
+        let issues = validate_config_text("title = \"x\"\ntemperature = 5.0\n", ConfigFormat::Toml);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "temperature");
+
+        let issues = validate_config_text("title: [unclosed\n", ConfigFormat::Yaml);
+        assert!(issues[0].message.starts_with("invalid YAML"));
+        assert!(issues[0].line.is_some());
+    }
+}
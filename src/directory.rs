@@ -1,7 +1,9 @@
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use std::fs;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+use crate::config_format::CONFIG_EXTENSIONS;
 
 /// Standard .th-chat directory name
 pub const TH_CHAT_DIR: &str = ".th-chat";
@@ -9,6 +11,9 @@ pub const TH_CHAT_DIR: &str = ".th-chat";
 /// Configuration file name
 pub const CONFIG_FILE: &str = "config.json";
 
+/// Configuration file stem, combined with any supported extension
+pub const CONFIG_FILE_STEM: &str = "config";
+
 /// Sessions subdirectory name
 pub const SESSIONS_DIR: &str = "sessions";
 
@@ -18,13 +23,16 @@ pub const PRESETS_DIR: &str = "presets";
 /// MCP configurations subdirectory name
 pub const MCP_DIR: &str = "mcp";
 
+/// Git ignore file written by `init`, keeping sessions out of version control
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
 /// Represents a .th-chat directory and its structure
 #[derive(Debug, Clone)]
 pub struct ThChatDirectory {
     /// Root path of the .th-chat directory
     pub root: PathBuf,
     
-    /// Path to config.json
+    /// Path to the config file (config.json, config.toml or config.yaml)
     pub config_file: PathBuf,
     
     /// Path to sessions/ subdirectory
@@ -40,7 +48,8 @@ pub struct ThChatDirectory {
 impl ThChatDirectory {
     /// Create a new ThChatDirectory from a root path
     pub fn new(root: PathBuf) -> Self {
-        let config_file = root.join(CONFIG_FILE);
+        let config_file = find_config_file(&root, CONFIG_FILE_STEM)
+            .unwrap_or_else(|| root.join(CONFIG_FILE));
         let sessions_dir = root.join(SESSIONS_DIR);
         let presets_dir = root.join(PRESETS_DIR);
         let mcp_dir = root.join(MCP_DIR);
@@ -86,14 +95,50 @@ impl ThChatDirectory {
         Ok(())
     }
     
+    /// Write a .gitignore excluding sessions, unless one already exists.
+    /// Returns whether the file was written.
+    pub fn write_gitignore(&self) -> Result<bool> {
+        let path = self.root.join(GITIGNORE_FILE);
+        if path.exists() {
+            return Ok(false);
+        }
+        
+        fs::write(&path, format!("{}/\n", SESSIONS_DIR))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(true)
+    }
+    
     /// Get the path to a session file
     pub fn session_file(&self, conversation_id: &str) -> PathBuf {
         self.sessions_dir.join(format!("{}.json", conversation_id))
     }
     
-    /// Get the path to a preset file
+    /// Get the path to a preset file, in whichever supported format exists
+    /// (new presets default to JSON)
     pub fn preset_file(&self, preset_name: &str) -> PathBuf {
-        self.presets_dir.join(format!("{}.json", preset_name))
+        find_config_file(&self.presets_dir, preset_name)
+            .unwrap_or_else(|| self.presets_dir.join(format!("{}.json", preset_name)))
+    }
+    
+    /// Config and preset files hidden by a file of the same name in a format that
+    /// wins, as (ignored file, file used instead) pairs
+    pub fn shadowed_config_files(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut stems = vec![(self.root.clone(), CONFIG_FILE_STEM.to_string())];
+        stems.extend(
+            self.list_presets()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|name| (self.presets_dir.clone(), name)),
+        );
+
+        let mut shadowed = Vec::new();
+        for (dir, stem) in stems {
+            let mut candidates = config_file_candidates(&dir, &stem).into_iter();
+            if let Some(used) = candidates.next() {
+                shadowed.extend(candidates.map(|ignored| (ignored, used.clone())));
+            }
+        }
+        shadowed
     }
     
     /// List available presets
@@ -110,7 +155,11 @@ impl ThChatDirectory {
             let entry = entry?;
             let path = entry.path();
             
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
+            let is_config = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| CONFIG_EXTENSIONS.contains(&ext));
+            if path.is_file() && is_config {
                 if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
                     presets.push(name.to_string());
                 }
@@ -118,6 +167,7 @@ impl ThChatDirectory {
         }
         
         presets.sort();
+        presets.dedup();
         Ok(presets)
     }
     
@@ -147,6 +197,31 @@ impl ThChatDirectory {
     }
 }
 
+/// Every existing `<dir>/<stem>.<ext>` with a supported config extension, in
+/// discovery order
+pub fn config_file_candidates(dir: &Path, stem: &str) -> Vec<PathBuf> {
+    CONFIG_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Find `<dir>/<stem>.<ext>` for the first supported config extension that exists,
+/// warning about files in other formats that it hides
+pub fn find_config_file(dir: &Path, stem: &str) -> Option<PathBuf> {
+    let mut candidates = config_file_candidates(dir, stem).into_iter();
+    let found = candidates.next()?;
+    for ignored in candidates {
+        warn!(
+            "Ignoring {}: {} is used instead",
+            ignored.display(),
+            found.display()
+        );
+    }
+    Some(found)
+}
+
 /// Find .th-chat directory by searching up the directory tree
 pub fn find_th_chat_dir() -> Option<ThChatDirectory> {
     let current_dir = std::env::current_dir().ok()?;
@@ -215,8 +290,48 @@ mod tests {
         assert!(directory.sessions_dir.exists());
         assert!(directory.presets_dir.exists());
         assert!(directory.mcp_dir.exists());
+        
+        assert!(directory.write_gitignore().unwrap());
+        assert!(!directory.write_gitignore().unwrap());
+        let gitignore = fs::read_to_string(directory.root.join(GITIGNORE_FILE)).unwrap();
+        assert_eq!(gitignore, "sessions/\n");
     }
     
+    #[test]
+    fn test_config_file_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let th_chat_path = temp_dir.path().join(TH_CHAT_DIR);
+        let directory = ThChatDirectory::new(th_chat_path.clone());
+        directory.create().unwrap();
+
+        fs::write(th_chat_path.join("config.toml"), "title = \"TOML\"").unwrap();
+        fs::write(directory.presets_dir.join("base.yaml"), "title: YAML").unwrap();
+        fs::write(directory.presets_dir.join("coding.json"), "{}").unwrap();
+        fs::write(directory.presets_dir.join("coding.toml"), "").unwrap();
+        fs::write(directory.presets_dir.join("notes.txt"), "").unwrap();
+
+        // Re-open so the config file is discovered
+        let directory = ThChatDirectory::new(th_chat_path);
+        assert!(directory.has_config());
+        assert_eq!(directory.config_file.file_name().unwrap(), "config.toml");
+
+        assert_eq!(directory.list_presets().unwrap(), vec!["base", "coding"]);
+        assert_eq!(directory.preset_file("base").file_name().unwrap(), "base.yaml");
+        // JSON wins when a preset exists in several formats
+        assert_eq!(directory.preset_file("coding").file_name().unwrap(), "coding.json");
+        // Missing presets default to JSON
+        assert_eq!(directory.preset_file("new").file_name().unwrap(), "new.json");
+
+        // The hidden TOML preset is reported
+        assert_eq!(
+            directory.shadowed_config_files(),
+            vec![(
+                directory.presets_dir.join("coding.toml"),
+                directory.presets_dir.join("coding.json")
+            )]
+        );
+    }
+
     #[test]
     fn test_find_th_chat_dir() {
         let temp_dir = TempDir::new().unwrap();
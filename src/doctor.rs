@@ -0,0 +1,366 @@
+use std::fs;
+use std::io::Read;
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config_layers::LayeredConfig;
+use crate::config_manager::{ConfigLoadOptions, ConfigManager, McpServer};
+use crate::config_schema::validate_config_file;
+use crate::directory::TH_CHAT_DIR;
+
+/// How long to wait for the Theater server to accept a connection
+const SERVER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long an MCP server must stay up to count as started
+const MCP_STARTUP_GRACE: Duration = Duration::from_millis(500);
+
+/// Outcome of a single diagnostic check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "[ OK ]",
+            CheckStatus::Warn => "[WARN]",
+            CheckStatus::Fail => "[FAIL]",
+        }
+    }
+}
+
+/// A diagnostic check result, with a suggested fix when it did not pass
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Settings the checks run against, taken from the command line
+#[derive(Debug, Default)]
+pub struct DoctorOptions {
+    pub server: String,
+    pub actor_manifest: Option<String>,
+    pub load_options: ConfigLoadOptions,
+}
+
+/// Run every check in startup order
+pub fn run_checks(config_manager: &ConfigManager, options: &DoctorOptions) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    results.push(check_legacy_session_file(Path::new(".")));
+    results.extend(check_config_files(config_manager));
+
+    let config = match config_manager.load_layered(&options.load_options) {
+        Ok(layered) => {
+            results.push(CheckResult::pass(
+                "Configuration",
+                format!("loaded from {}", layered.source()),
+            ));
+            Some(layered)
+        }
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Configuration",
+                format!("{:#}", e),
+                "Fix the reported file, or run 'th-chat config validate' for details",
+            ));
+            None
+        }
+    };
+
+    results.push(check_server(&options.server));
+
+    // The manifest can still be checked against the default when the config is broken
+    results.push(check_actor_manifest(
+        config_manager,
+        options.actor_manifest.as_deref(),
+        config.as_ref(),
+    ));
+
+    if let Some(layered) = &config {
+        results.extend(layered.config.mcp_servers.iter().map(check_mcp_server));
+    }
+
+    results.push(check_sessions_directory(config_manager));
+    results
+}
+
+/// A `.th-chat` *file* is session state from before the `.th-chat/` directory layout,
+/// and stops the directory from being created
+pub fn check_legacy_session_file(dir: &Path) -> CheckResult {
+    let name = "Legacy session file";
+    let path = dir.join(TH_CHAT_DIR);
+
+    if !path.is_file() {
+        return CheckResult::pass(name, "none");
+    }
+
+    let detail = match fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|value| value.get("conversation_id").cloned())
+    {
+        Some(conversation_id) => format!(
+            "{} holds legacy session state (conversation {})",
+            path.display(),
+            conversation_id
+        ),
+        None => format!("{} is a file, not a directory", path.display()),
+    };
+    CheckResult::fail(
+        name,
+        detail,
+        format!(
+            "Move it aside (mv {0} {0}.legacy) and run 'th-chat init'",
+            TH_CHAT_DIR
+        ),
+    )
+}
+
+fn check_config_files(config_manager: &ConfigManager) -> Vec<CheckResult> {
+    let files = config_manager.config_files();
+    if files.is_empty() {
+        return vec![CheckResult::warn(
+            "Config files",
+            "none found; built-in defaults are used",
+            "Run 'th-chat init' to create a config",
+        )];
+    }
+
+    let shadowed = config_manager
+        .shadowed_config_files()
+        .into_iter()
+        .map(|(ignored, used)| {
+            CheckResult::fail(
+                format!("Config {}", ignored.display()),
+                format!("ignored; {} is used instead", used.display()),
+                "Remove one of the two files, or merge them into one",
+            )
+        });
+
+    files
+        .iter()
+        .map(|file| {
+            let name = format!("Config {}", file.display());
+            match validate_config_file(file) {
+                Ok(issues) if issues.is_empty() => CheckResult::pass(name, "valid"),
+                Ok(issues) => CheckResult::fail(
+                    name,
+                    format!("{} issue(s), first: {}", issues.len(), issues[0]),
+                    format!("Run 'th-chat config validate {}'", file.display()),
+                ),
+                Err(e) => CheckResult::fail(name, format!("{:#}", e), "Check file permissions"),
+            }
+        })
+        .chain(shadowed)
+        .collect()
+}
+
+fn check_server(server: &str) -> CheckResult {
+    let name = "Theater server";
+    let address: SocketAddr = match server.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            return CheckResult::fail(
+                name,
+                format!("invalid address '{}': {}", server, e),
+                "Pass --server as host:port, e.g. 127.0.0.1:9000",
+            )
+        }
+    };
+
+    match TcpStream::connect_timeout(&address, SERVER_TIMEOUT) {
+        Ok(_) => CheckResult::pass(name, format!("reachable at {}", address)),
+        Err(e) => CheckResult::fail(
+            name,
+            format!("cannot connect to {}: {}", address, e),
+            "Start the Theater server, or point --server (THEATER_SERVER_ADDRESS) at it",
+        ),
+    }
+}
+
+fn check_actor_manifest(
+    config_manager: &ConfigManager,
+    cli_override: Option<&str>,
+    layered: Option<&LayeredConfig>,
+) -> CheckResult {
+    let name = "Actor manifest";
+    match config_manager.resolve_actor_manifest(cli_override, layered) {
+        Ok(manifest) => CheckResult::pass(name, manifest),
+        Err(e) => CheckResult::fail(
+            name,
+            format!("{:#}", e),
+            "Pass --actor-manifest, set actor_manifest in the config, or set THEATER_ACTOR_REGISTRY",
+        ),
+    }
+}
+
+/// Check an MCP server command exists and stays up once started
+pub fn check_mcp_server(server: &McpServer) -> CheckResult {
+    let name = format!(
+        "MCP server {}",
+        server.name.as_deref().unwrap_or(&server.config.command)
+    );
+
+    let resolved = match server.config.resolve() {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            return CheckResult::fail(
+                name,
+                e.to_string(),
+                "Install it, or set the full path in mcp_servers[].config.command",
+            )
+        }
+    };
+
+    let mut child = match Command::new(&resolved.command)
+        .args(&resolved.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            return CheckResult::fail(
+                name,
+                format!("failed to start {}: {}", resolved.command, e),
+                "Check the file is executable",
+            )
+        }
+    };
+
+    // A stdio MCP server waits for requests; exiting straight away means it failed
+    let started = Instant::now();
+    while started.elapsed() < MCP_STARTUP_GRACE {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = child.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr);
+                }
+                debug!("MCP server {} exited: {}", resolved.command, stderr);
+                let first_line = stderr.lines().next().unwrap_or("").trim();
+                return CheckResult::fail(
+                    name,
+                    format!("exited immediately ({}) {}", status, first_line),
+                    "Run the command by hand to see why; check its arguments in the config",
+                );
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return CheckResult::fail(name, e.to_string(), "Run the command by hand"),
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+    CheckResult::pass(name, format!("started {}", resolved.command))
+}
+
+fn check_sessions_directory(config_manager: &ConfigManager) -> CheckResult {
+    let name = "Sessions directory";
+    let Some(directory) = config_manager.get_sessions_directory() else {
+        return CheckResult::warn(
+            name,
+            "no .th-chat directory found (one is created on first run)",
+            "Run 'th-chat init'",
+        );
+    };
+
+    let probe = directory.sessions_dir.join(".doctor-write-test");
+    let writable = fs::create_dir_all(&directory.sessions_dir)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe));
+    match writable {
+        Ok(()) => CheckResult::pass(name, directory.sessions_dir.display().to_string()),
+        Err(e) => CheckResult::fail(
+            name,
+            format!(
+                "{} is not writable: {}",
+                directory.sessions_dir.display(),
+                e
+            ),
+            "Fix the directory permissions",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_manager::McpConfig;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_legacy_session_file() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(
+            check_legacy_session_file(temp_dir.path()).status,
+            CheckStatus::Pass
+        );
+
+        fs::write(
+            temp_dir.path().join(TH_CHAT_DIR),
+            r#"{"conversation_id":"abc","store_id":"s","created_at":0,"last_accessed":0}"#,
+        )
+        .unwrap();
+        let result = check_legacy_session_file(temp_dir.path());
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.contains("abc"));
+        assert!(result.fix.is_some());
+    }
+
+    #[test]
+    fn test_check_mcp_server_missing_command() {
+        let server = McpServer {
+            name: None,
+            actor_id: None,
+            config: McpConfig {
+                command: "th-chat-no-such-mcp-server".to_string(),
+                args: vec![],
+            },
+            tools: None,
+            allowed_tools: None,
+            denied_tools: Vec::new(),
+        };
+        assert_eq!(check_mcp_server(&server).status, CheckStatus::Fail);
+    }
+}
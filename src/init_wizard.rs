@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, Write};
+
+use crate::config_manager::{ConversationConfig, KnownMcpServer, McpConfig, McpServer};
+
+/// Providers offered by the wizard, with the model suggested for each
+const PROVIDERS: &[(&str, &str)] = &[
+    ("google", "gemini-2.5-flash-preview-04-17"),
+    ("anthropic", "claude-sonnet-4-20250514"),
+];
+
+/// Arguments given to filesystem MCP servers selected in the wizard. `PWD` is unset
+/// for processes not started from a shell, so it falls back to `.`
+const MCP_SERVER_ARGS: &[&str] = &["--allowed-dirs", "${PWD:-.}"];
+
+/// Interactive prompts for `th-chat init --interactive`
+pub struct InitWizard<R, W> {
+    input: R,
+    output: W,
+    at_end_of_input: bool,
+}
+
+impl<R: BufRead, W: Write> InitWizard<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            at_end_of_input: false,
+        }
+    }
+
+    /// Ask for each setting and preview the result. Returns `None` if the user
+    /// declines to write it.
+    pub fn run(&mut self, installed: &[KnownMcpServer]) -> Result<Option<ConversationConfig>> {
+        // The default MCP servers are replaced by the selection below
+        let mut config = ConversationConfig::default(String::new());
+
+        let provider_names: Vec<&str> = PROVIDERS.iter().map(|(name, _)| *name).collect();
+        let provider = self.ask(
+            &format!("Provider [{}]", provider_names.join("/")),
+            Some(PROVIDERS[0].0),
+        )?;
+        let suggested_model = PROVIDERS
+            .iter()
+            .find(|(name, _)| *name == provider)
+            .map(|(_, model)| *model);
+        let model = loop {
+            let model = self.ask("Model", suggested_model)?;
+            if !model.is_empty() {
+                break model;
+            }
+            if self.at_end_of_input {
+                anyhow::bail!("No model given for provider '{}'", provider);
+            }
+            writeln!(
+                self.output,
+                "A model is required for provider '{}'",
+                provider
+            )?;
+        };
+        config.model_config.provider = provider;
+        config.model_config.model = model;
+
+        config.temperature = loop {
+            let answer = self.ask("Temperature (0-2, blank for provider default)", None)?;
+            if answer.is_empty() {
+                break None;
+            }
+            match answer.parse::<f32>() {
+                Ok(value) if (0.0..=2.0).contains(&value) => break Some(value),
+                _ => writeln!(self.output, "Enter a number between 0 and 2")?,
+            }
+        };
+
+        let prompt = self.ask(
+            "System prompt (text, @file for a prompt file, blank for none)",
+            None,
+        )?;
+        if let Some(file) = prompt.strip_prefix('@') {
+            config.system_prompt_file = Some(file.trim().to_string());
+        } else if !prompt.is_empty() {
+            config.system_prompt = Some(prompt);
+        }
+
+        config.mcp_servers = self.ask_mcp_servers(installed)?;
+
+        writeln!(self.output)?;
+        writeln!(self.output, "Configuration preview:")?;
+        writeln!(self.output, "{}", serde_json::to_string_pretty(&config)?)?;
+        writeln!(self.output)?;
+
+        Ok(self
+            .confirm("Write this configuration?", true)?
+            .then_some(config))
+    }
+
+    fn ask_mcp_servers(&mut self, installed: &[KnownMcpServer]) -> Result<Vec<McpServer>> {
+        let mut commands = Vec::new();
+
+        if installed.is_empty() {
+            writeln!(self.output, "No known MCP servers found on $PATH")?;
+        }
+        for server in installed {
+            let question = format!("Enable {} ({})?", server.command, server.description);
+            if self.confirm(&question, true)? {
+                commands.push(server.command.to_string());
+            }
+        }
+
+        let others = self.ask("Other MCP server commands (comma separated)", None)?;
+        commands.extend(
+            others
+                .split(',')
+                .map(str::trim)
+                .filter(|command| !command.is_empty())
+                .map(str::to_string),
+        );
+
+        Ok(commands
+            .into_iter()
+            .map(|command| McpServer {
+                name: None,
+                actor_id: None,
+                config: McpConfig {
+                    command,
+                    args: MCP_SERVER_ARGS.iter().map(|arg| arg.to_string()).collect(),
+                },
+                tools: None,
+                allowed_tools: None,
+                denied_tools: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// Prompt for a line of input, falling back to `default` when it is blank
+    fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        match default {
+            Some(default) => write!(self.output, "{} ({}): ", question, default)?,
+            None => write!(self.output, "{}: ", question)?,
+        }
+        self.output.flush()?;
+
+        let mut line = String::new();
+        let read = self
+            .input
+            .read_line(&mut line)
+            .context("Failed to read answer")?;
+        self.at_end_of_input = read == 0;
+        let answer = line.trim();
+        Ok(match (answer.is_empty(), default) {
+            (true, Some(default)) => default.to_string(),
+            _ => answer.to_string(),
+        })
+    }
+
+    /// Yes/no prompt; a blank answer picks `default`
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        let answer = self.ask(&format!("{} [{}]", question, hint), None)?;
+        Ok(match answer.to_lowercase().chars().next() {
+            Some('y') => true,
+            Some('n') => false,
+            _ => default,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_manager::KNOWN_MCP_SERVERS;
+    use std::io::Cursor;
+
+    fn run(answers: &str) -> Option<ConversationConfig> {
+        let mut output = Vec::new();
+        InitWizard::new(Cursor::new(answers.to_string()), &mut output)
+            .run(KNOWN_MCP_SERVERS)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wizard_answers() {
+        let config =
+            run("anthropic\n\n3\n0.3\n@prompts/system.md\nn\ny\ngit-mcp-server\n\n").unwrap();
+
+        assert_eq!(config.model_config.provider, "anthropic");
+        assert_eq!(config.model_config.model, "claude-sonnet-4-20250514");
+        assert_eq!(config.temperature, Some(0.3));
+        assert_eq!(config.system_prompt, None);
+        assert_eq!(
+            config.system_prompt_file.as_deref(),
+            Some("prompts/system.md")
+        );
+
+        let commands: Vec<&str> = config
+            .mcp_servers
+            .iter()
+            .map(|server| server.config.command.as_str())
+            .collect();
+        assert_eq!(commands, vec!["fs-mcp-server", "git-mcp-server"]);
+    }
+
+    #[test]
+    fn test_wizard_defaults_and_decline() {
+        // End of input accepts every default
+        let config = run("").unwrap();
+        assert_eq!(config.model_config.provider, "google");
+        assert_eq!(config.temperature, None);
+        assert_eq!(config.mcp_servers.len(), KNOWN_MCP_SERVERS.len());
+
+        assert!(run("\n\n\n\n\n\n\nn\n").is_none());
+
+        let mut output = Vec::new();
+        assert!(InitWizard::new(Cursor::new("custom\n"), &mut output)
+            .run(&[])
+            .is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Expand placeholders in a string using the process environment.
+///
+/// Supported forms:
+/// - `${VAR}`: value of an environment variable (error when unset)
+/// - `${VAR:-default}`: value of the variable, or `default` when unset or empty
+/// - `${file:path}`: contents of a file with trailing newlines removed; relative
+///   paths are resolved against `base_dir`
+/// - `$${`: a literal `${`
+pub fn interpolate(input: &str, base_dir: Option<&Path>) -> Result<String> {
+    interpolate_with(input, base_dir, |name| std::env::var(name).ok())
+}
+
+/// Expand placeholders using the given environment lookup function
+pub fn interpolate_with<F>(input: &str, base_dir: Option<&Path>, lookup: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if let Some(after) = tail.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow::anyhow!("Unterminated placeholder in '{}'", input))?;
+            output.push_str(&expand_placeholder(&after[..end], base_dir, &lookup)?);
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+            rest = &tail[1..];
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Expand the contents of a single `${...}` placeholder
+fn expand_placeholder<F>(placeholder: &str, base_dir: Option<&Path>, lookup: &F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some(path) = placeholder.strip_prefix("file:") {
+        let path = resolve_file_path(path.trim(), base_dir);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read '{}' for ${{file:}}", path.display()))?;
+        return Ok(content.trim_end_matches(['\n', '\r']).to_string());
+    }
+
+    let (name, default) = match placeholder.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (placeholder, None),
+    };
+    if name.is_empty() {
+        anyhow::bail!("Empty placeholder '${{{}}}'", placeholder);
+    }
+
+    match (lookup(name), default) {
+        (Some(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Some(value), _) => Ok(value),
+        (None, Some(default)) => Ok(default.to_string()),
+        (None, None) => anyhow::bail!("Environment variable '{}' is not set", name),
+    }
+}
+
+/// Resolve a `${file:...}` path, expanding `~/` and relative paths
+fn resolve_file_path(path: &str, base_dir: Option<&Path>) -> PathBuf {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(stripped);
+        }
+    }
+
+    let path = PathBuf::from(path);
+    match base_dir {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    }
+}
+
+/// Expand placeholders in every string of a JSON document. Object keys are left
+/// untouched. Errors name the field that failed, e.g. `mcp_servers[0].config.args[1]`.
+pub fn interpolate_json(value: &mut Value, base_dir: Option<&Path>) -> Result<()> {
+    interpolate_json_at(value, base_dir, &mut String::new())
+}
+
+fn interpolate_json_at(
+    value: &mut Value,
+    base_dir: Option<&Path>,
+    path: &mut String,
+) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            if s.contains('$') {
+                *s = interpolate(s, base_dir)
+                    .with_context(|| format!("Failed to interpolate '{}'", display_field(path)))?;
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", index));
+                interpolate_json_at(item, base_dir, path)?;
+                path.truncate(len);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                interpolate_json_at(item, base_dir, path)?;
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Make the relative paths of `${file:...}` placeholders absolute against `base_dir`,
+/// so a document can move to a file in another directory. Returns the fields that
+/// were rewritten.
+pub fn rebase_file_placeholders_json(value: &mut Value, base_dir: &Path) -> Vec<String> {
+    let mut rewritten = Vec::new();
+    rebase_json_at(value, base_dir, &mut String::new(), &mut rewritten);
+    rewritten
+}
+
+fn rebase_json_at(
+    value: &mut Value,
+    base_dir: &Path,
+    path: &mut String,
+    rewritten: &mut Vec<String>,
+) {
+    match value {
+        Value::String(s) => {
+            if let Some(rebased) = rebase_file_placeholders(s, base_dir) {
+                *s = rebased;
+                rewritten.push(display_field(path).to_string());
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", index));
+                rebase_json_at(item, base_dir, path, rewritten);
+                path.truncate(len);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                rebase_json_at(item, base_dir, path, rewritten);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Rebase the relative `${file:...}` paths in one string, or `None` when there are none
+fn rebase_file_placeholders(input: &str, base_dir: &Path) -> Option<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    let mut changed = false;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if let Some(after) = tail.strip_prefix("$${") {
+            output.push_str("$${");
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("${") {
+            let Some(end) = after.find('}') else {
+                rest = tail;
+                break;
+            };
+            match after[..end].strip_prefix("file:").map(str::trim) {
+                Some(path)
+                    if !path.is_empty()
+                        && !path.starts_with('~')
+                        && Path::new(path).is_relative() =>
+                {
+                    output.push_str(&format!("${{file:{}}}", base_dir.join(path).display()));
+                    changed = true;
+                }
+                _ => output.push_str(&tail[..end + 3]),
+            }
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+            rest = &tail[1..];
+        }
+    }
+
+    output.push_str(rest);
+    changed.then_some(output)
+}
+
+fn display_field(path: &str) -> &str {
+    if path.is_empty() {
+        "<root>"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/user".to_string()),
+            "PROJECT" => Some("demo".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        let expand = |s: &str| interpolate_with(s, None, lookup);
+
+        assert_eq!(expand("plain $5 text").unwrap(), "plain $5 text");
+        assert_eq!(
+            expand("${HOME}/work/${PROJECT}").unwrap(),
+            "/home/user/work/demo"
+        );
+        assert_eq!(expand("${MISSING:-fallback}").unwrap(), "fallback");
+        assert_eq!(expand("${EMPTY:-fallback}").unwrap(), "fallback");
+        assert_eq!(expand("${PROJECT:-fallback}").unwrap(), "demo");
+        assert_eq!(
+            expand("$${HOME} is ${HOME}").unwrap(),
+            "${HOME} is /home/user"
+        );
+
+        assert!(expand("${MISSING}").is_err());
+        assert!(expand("${HOME").is_err());
+        assert!(expand("${}").is_err());
+    }
+
+    #[test]
+    fn test_interpolate_file() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("token"), "secret\n").unwrap();
+
+        let expanded =
+            interpolate_with("Bearer ${file:token}", Some(temp_dir.path()), lookup).unwrap();
+        assert_eq!(expanded, "Bearer secret");
+        assert!(interpolate_with("${file:missing}", Some(temp_dir.path()), lookup).is_err());
+    }
+
+    #[test]
+    fn test_interpolate_json_reports_field() {
+        let mut value = json!({
+            "title": "$${literal}",
+            "mcp_servers": [{ "config": { "args": ["ok", "${TH_CHAT_TEST_UNSET_VARIABLE}"] } }]
+        });
+
+        let err = interpolate_json(&mut value, None).unwrap_err();
+        assert!(format!("{:#}", err).contains("mcp_servers[0].config.args[1]"));
+
+        let mut value = json!({ "title": "$${literal}", "max_tokens": 10 });
+        interpolate_json(&mut value, None).unwrap();
+        assert_eq!(value["title"], "${literal}");
+    }
+
+    #[test]
+    fn test_rebase_file_placeholders() {
+        let mut value = json!({
+            "title": "$${file:literal}",
+            "mcp_servers": [{ "config": { "env": {
+                "TOKEN": "Bearer ${file:token} ${HOME}",
+                "HOME_TOKEN": "${file:~/token}",
+                "ABSOLUTE": "${file:/etc/token}"
+            } } }]
+        });
+
+        let rewritten = rebase_file_placeholders_json(&mut value, Path::new("/config"));
+        assert_eq!(rewritten, vec!["mcp_servers[0].config.env.TOKEN"]);
+        let env = &value["mcp_servers"][0]["config"]["env"];
+        assert_eq!(env["TOKEN"], "Bearer ${file:/config/token} ${HOME}");
+        assert_eq!(env["HOME_TOKEN"], "${file:~/token}");
+        assert_eq!(value["title"], "$${file:literal}");
+    }
+}
@@ -1,10 +1,22 @@
 pub mod config;
+pub mod config_edit;
+pub mod config_format;
+pub mod config_layers;
 pub mod config_manager;
+pub mod config_schema;
 pub mod directory;
+pub mod doctor;
+pub mod init_wizard;
+pub mod interpolation;
+pub mod mcp_registry;
 pub mod persistence;
+pub mod prompt_template;
 pub mod session_manager;
+pub mod tool_calls;
+pub mod tool_inventory;
+pub mod tool_policy;
 
 // Re-export commonly used types
 pub use config_manager::{ConversationConfig, ConfigManager, ConfigLoadOptions};
 pub use directory::{ThChatDirectory, create_local_th_chat_dir, create_global_th_chat_dir};
-pub use session_manager::{SessionManager, SessionData, SessionInfo};
+pub use session_manager::{SessionManager, SessionData, SessionInfo, SessionUsage};
@@ -13,16 +13,29 @@ use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt};
 mod app;
 mod chat;
 mod config;
+mod config_edit;
+mod config_format;
+mod config_layers;
 mod config_manager;
+mod config_schema;
 mod directory;
+mod doctor;
+mod init_wizard;
+mod interpolation;
+mod mcp_registry;
 mod persistence;
+mod prompt_template;
 mod session_manager;
+mod tool_calls;
+mod tool_inventory;
+mod tool_policy;
+mod tool_renderers;
 mod ui;
 
 use app::App;
-use config::{Args, Command, CompatibleArgs, SessionAction};
+use config::{Args, Command, CompatibleArgs, ConfigAction, McpAction, PresetAction, SessionAction};
 use config_manager::ConversationConfig;
-use config_manager::{ConfigLoadOptions, ConfigManager};
+use config_manager::{ConfigManager, ConfigScope};
 use directory::ThChatDirectory;
 use directory::{create_global_th_chat_dir, create_local_th_chat_dir};
 use session_manager::{SessionInfo, SessionManager};
@@ -37,6 +50,7 @@ struct ExtendedArgs {
     pub clear_session: bool,
     pub config: ConversationConfig,
     pub sessions_directory: Option<ThChatDirectory>,
+    pub actor_manifest: String,
 }
 
 impl ExtendedArgs {
@@ -63,6 +77,9 @@ impl ExtendedArgs {
             debug: self.debug,
             mcp_config: mcp_config_path,
             clear_session: self.clear_session,
+            model_pricing: self.config.model_pricing.clone(),
+            context_window: self.config.model_config.effective_context_window(),
+            actor_manifest: self.actor_manifest.clone(),
         }
     }
 }
@@ -144,7 +161,7 @@ async fn main() -> Result<()> {
 
     // Handle management commands first
     if let Some(command) = &args.command {
-        return handle_command(command).await;
+        return handle_command(command, &args).await;
     }
 
     // Setup terminal
@@ -165,6 +182,8 @@ async fn main() -> Result<()> {
 
     if let Err(err) = res {
         println!("{:?}", err);
+        println!();
+        println!("Run 'th-chat doctor' to check the server, actor manifest and MCP servers.");
     }
 
     Ok(())
@@ -177,14 +196,26 @@ async fn run_app(
 ) -> Result<()> {
     // Load configuration using new system
     let config_manager = ConfigManager::new();
-    let config_options = ConfigLoadOptions {
-        config_file: args.config.clone(),
-        preset: args.preset.clone(),
-    };
+    let config_options = args.config_load_options();
 
-    let (conversation_config, config_source) = config_manager
-        .load_config(&config_options)
+    let layered = config_manager
+        .load_layered(&config_options)
         .context("Failed to load configuration")?;
+    let mut conversation_config = layered.config.clone();
+    let config_source = layered.source();
+
+    // Render the system prompt (file and template variables) once per session start.
+    // Relative paths resolve against the directory of the layer that set the prompt.
+    let prompt_field = if conversation_config.system_prompt_file.is_some() {
+        "system_prompt_file"
+    } else {
+        "system_prompt"
+    };
+    let prompt_base_dir = config_manager.field_base_dir(&layered, prompt_field)?;
+    let template_context = prompt_template::TemplateContext::from_environment()?;
+    conversation_config
+        .render_system_prompt(&prompt_base_dir, &template_context)
+        .context("Failed to render system prompt")?;
 
     info!("Using configuration from: {}", config_source);
     debug!("Loaded configuration: {:?}", conversation_config);
@@ -241,6 +272,10 @@ async fn run_app(
         new_session
     };
 
+    // Resolve the chat-state actor manifest; failures are reported on the loading screen
+    let actor_manifest =
+        config_manager.resolve_actor_manifest(args.actor_manifest.as_deref(), Some(&layered));
+
     // Create an extended args struct with the loaded configuration
     let extended_args = ExtendedArgs {
         server: args.server.clone(),
@@ -250,6 +285,7 @@ async fn run_app(
 
         config: conversation_config,
         sessions_directory: config_manager.get_sessions_directory().cloned(),
+        actor_manifest: actor_manifest.as_ref().cloned().unwrap_or_default(),
     };
 
     // Convert to compatible format for existing code
@@ -291,9 +327,25 @@ async fn run_app(
 
     // Small delay to show the initialization step
     //    tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-    app.complete_current_step();
+    let unresolved_mcp_servers = extended_args.config.unresolved_mcp_servers();
+    if unresolved_mcp_servers.is_empty() {
+        app.complete_current_step();
+    } else {
+        for problem in &unresolved_mcp_servers {
+            warn!("{}", problem);
+        }
+        app.warn_current_step(format!(
+            "{} (server skipped)",
+            unresolved_mcp_servers.join("; ")
+        ));
+    }
     terminal.draw(|f| ui::render(f, &mut app, &compat_args))?;
 
+    // Give user time to see the warning
+    if !unresolved_mcp_servers.is_empty() {
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    }
+
     // Step 1: Connect to server
     app.start_loading_step(
         1,
@@ -330,6 +382,15 @@ async fn run_app(
     );
     terminal.draw(|f| ui::render(f, &mut app, &compat_args))?;
 
+    if let Err(e) = actor_manifest {
+        error!("Failed to resolve actor manifest: {:?}", e);
+        app.fail_current_step(format!("Actor manifest not found: {}", e));
+        terminal.draw(|f| ui::render(f, &mut app, &compat_args))?;
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        return Err(e);
+    }
+
     info!("Starting chat-state actor...");
     let actor_id = match chat::ChatManager::start_actor_with_session(
         &mut connection,
@@ -411,6 +472,19 @@ async fn run_app(
             app.fail_current_step(format!("Metadata retrieval failed: {}", e));
         }
     }
+
+    // Record which MCP servers the actor loaded, for the /tools view
+    let inventory = chat_manager.initial_tool_inventory();
+    info!(
+        "Actor reported {} tools from {} MCP servers",
+        inventory.tool_count(),
+        inventory.servers.len()
+    );
+    app.tool_policy = tool_policy::ToolPolicy::from_config(&extended_args.config);
+    app.tool_policy.deny(&chat_manager.denied_confirm_tools);
+    app.tool_renderers =
+        tool_renderers::RendererRegistry::from_config(&extended_args.config.tool_renderers);
+    app.set_tool_inventory(inventory);
     terminal.draw(|f| ui::render(f, &mut app, &compat_args))?;
     tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -453,6 +527,19 @@ async fn run_app(
 
     // Finish loading
     app.finish_loading();
+
+    // Approval needs an actor that holds matching calls; without it they are denied
+    let denied_confirm_tools = &chat_manager.denied_confirm_tools;
+    if !denied_confirm_tools.is_empty() {
+        app.add_error_entry(chat::ErrorInfo {
+            code: "tool_approval_unsupported".to_string(),
+            message: format!(
+                "The chat-state actor does not support tool approval, so calls matching confirm_tools ({}) are denied. Use an actor that implements it to approve them.",
+                denied_confirm_tools.join(", ")
+            ),
+            details: None,
+        });
+    }
     info!("Application ready for session '{}'", session_name);
 
     // Start main application loop with session context
@@ -462,6 +549,7 @@ async fn run_app(
         &mut app,
         &mut chat_manager,
         &compat_args,
+        &extended_args.config,
         &session_manager,
         &mut session_data,
     )
@@ -481,6 +569,7 @@ async fn run_chat_session(
     app: &mut App,
     chat_manager: &mut chat::ChatManager,
     args: &CompatibleArgs,
+    config: &ConversationConfig,
     session_manager: &SessionManager,
     session_data: &mut session_manager::SessionData,
 ) -> Result<()> {
@@ -488,7 +577,14 @@ async fn run_chat_session(
 
     // Enhanced app.run that includes session management
     let result = app
-        .run_with_session_context(terminal, chat_manager, args, session_manager, session_data)
+        .run_with_session_context(
+            terminal,
+            chat_manager,
+            args,
+            config,
+            session_manager,
+            session_data,
+        )
         .await;
 
     // Save final session state before exiting
@@ -501,6 +597,342 @@ async fn run_chat_session(
     result
 }
 
+/// Run `init --interactive`, writing the chosen configuration. Returns false if
+/// the user declined to write it.
+fn run_init_wizard(
+    directory: &ThChatDirectory,
+    installed: &[config_manager::KnownMcpServer],
+) -> Result<bool> {
+    if directory.has_config() {
+        print!(
+            "{} already exists. Overwrite it? (y/N): ",
+            directory.config_file.display()
+        );
+        std::io::Write::flush(&mut std::io::stdout())?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if !input.trim().to_lowercase().starts_with('y') {
+            return Ok(false);
+        }
+    }
+
+    let stdin = io::stdin();
+    let mut wizard = init_wizard::InitWizard::new(stdin.lock(), io::stdout());
+    let Some(config) = wizard.run(installed)? else {
+        return Ok(false);
+    };
+
+    config_edit::write_config_document(&directory.config_file, &serde_json::to_value(&config)?)?;
+    Ok(true)
+}
+
+/// Handle MCP server management commands
+fn handle_mcp_command(action: &McpAction, args: &Args) -> Result<()> {
+    let config_manager = ConfigManager::new();
+
+    match action {
+        McpAction::List => {
+            let definitions = config_manager.mcp_definitions()?;
+            if definitions.is_empty() {
+                println!(
+                    "No MCP servers defined. Add one with 'th-chat mcp add <name> <command>'."
+                );
+                return Ok(());
+            }
+
+            println!("MCP servers:");
+            for (definition, path) in definitions {
+                let installed = if config_manager::find_executable(&definition.command).is_some() {
+                    "✅"
+                } else {
+                    "❌"
+                };
+                println!(
+                    "  {} {}: {} {}",
+                    installed,
+                    definition.name,
+                    definition.command,
+                    definition.args.join(" ")
+                );
+                if let Some(description) = &definition.description {
+                    println!("       {}", description);
+                }
+                println!("       ({})", path.display());
+            }
+        }
+
+        McpAction::Add {
+            name,
+            command,
+            args: server_args,
+            description,
+            force,
+            scope,
+        } => {
+            let directory = config_manager.target_directory(scope.scope())?;
+            let path = mcp_registry::definition_path(&directory.mcp_dir, name)?;
+            if path.exists() && !force {
+                anyhow::bail!(
+                    "MCP server '{}' already exists at {} (use --force to replace it)",
+                    name,
+                    path.display()
+                );
+            }
+
+            let definition = mcp_registry::McpDefinition {
+                name: name.clone(),
+                description: description.clone(),
+                command: command.clone(),
+                args: server_args.clone(),
+            };
+            let path = mcp_registry::save_definition(&directory.mcp_dir, &definition)?;
+            println!("✅ Added MCP server '{}' at {}", name, path.display());
+            if config_manager::find_executable(command).is_none() {
+                println!("   Warning: '{}' was not found on $PATH", command);
+            }
+            println!(
+                "   Reference it from a preset with \"mcp_servers\": [\"{}\"]",
+                name
+            );
+        }
+
+        McpAction::Remove { name, scope } => {
+            let path = match scope.scope() {
+                ConfigScope::Auto => config_manager.find_mcp_definition(name)?.1,
+                target => {
+                    let directory = config_manager.target_directory(target)?;
+                    mcp_registry::definition_path(&directory.mcp_dir, name)?
+                }
+            };
+            if !path.exists() {
+                anyhow::bail!("MCP server '{}' not found at {}", name, path.display());
+            }
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete {}", path.display()))?;
+            println!("🗑️  Removed MCP server '{}' ({})", name, path.display());
+        }
+
+        McpAction::Test { name, timeout } => {
+            let server = match config_manager.find_mcp_definition(name) {
+                Ok((definition, path)) => {
+                    let mut value = definition.to_server_value();
+                    interpolation::interpolate_json(&mut value, path.parent())?;
+                    serde_json::from_value::<config_manager::McpServer>(value)?
+                }
+                Err(e) => {
+                    // Fall back to a server configured directly in the resolved config
+                    let layered = config_manager.load_layered(&args.config_load_options())?;
+                    layered
+                        .config
+                        .mcp_servers
+                        .into_iter()
+                        .find(|server| {
+                            config_layers::mcp_server_name(
+                                &serde_json::to_value(server).unwrap_or_default(),
+                            ) == *name
+                        })
+                        .ok_or(e)?
+                }
+            };
+
+            let resolved = server.config.resolve()?;
+            println!(
+                "Starting {} {}...",
+                resolved.command,
+                resolved.args.join(" ")
+            );
+            let tools =
+                mcp_registry::probe_server(&resolved, std::time::Duration::from_secs(*timeout))
+                    .with_context(|| format!("MCP server '{}' failed", name))?;
+
+            println!(
+                "✅ '{}' started and provides {} tool(s):",
+                name,
+                tools.len()
+            );
+            for tool in tools {
+                match tool.description {
+                    Some(description) => println!("   - {}: {}", tool.name, description),
+                    None => println!("   - {}", tool.name),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle preset management commands
+fn handle_preset_command(action: &PresetAction, args: &Args) -> Result<()> {
+    let config_manager = ConfigManager::new();
+
+    match action {
+        PresetAction::List => {
+            let presets = config_manager.list_presets()?;
+
+            if presets.is_empty() {
+                println!("No presets found. Run 'th-chat init' to create example presets.");
+            } else {
+                println!("Available presets:");
+                for (name, source) in presets {
+                    println!("  {} ({})", name, source);
+                }
+            }
+        }
+
+        PresetAction::Show {
+            name,
+            resolved,
+            scope,
+        } => {
+            let path = config_manager.locate_preset(name, scope.scope())?;
+            println!("# {}", path.display());
+            if *resolved {
+                let layered = config_manager.load_preset(name)?;
+                println!("{}", serde_json::to_string_pretty(&layered.merged.value)?);
+            } else {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                println!("{}", content.trim_end());
+            }
+        }
+
+        PresetAction::New {
+            name,
+            snapshot,
+            extends,
+            format,
+            scope,
+        } => {
+            let directory = config_manager.target_directory(scope.scope())?;
+            if directory.preset_file(name).exists() {
+                anyhow::bail!(
+                    "Preset '{}' already exists in {}",
+                    name,
+                    directory.presets_dir.display()
+                );
+            }
+
+            // A snapshot keeps placeholders as written and leaves out built-in defaults
+            let document = if *snapshot {
+                config_manager.load_unexpanded(
+                    &args.config_load_options(),
+                    &config_manager::PathBases::for_preset(directory),
+                )?
+            } else {
+                match extends {
+                    Some(parent) => serde_json::json!({ "extends": parent }),
+                    None => serde_json::json!({}),
+                }
+            };
+
+            std::fs::create_dir_all(&directory.presets_dir)
+                .with_context(|| format!("Failed to create {}", directory.presets_dir.display()))?;
+            let path = directory
+                .presets_dir
+                .join(format!("{}.{}", name, format.extension()));
+            config_edit::write_config_document(&path, &document)?;
+            println!("✅ Created preset '{}' at {}", name, path.display());
+        }
+
+        PresetAction::Copy { from, to, scope } => {
+            let source = config_manager.locate_preset(from, ConfigScope::Auto)?;
+            let directory = config_manager.target_directory(scope.scope())?;
+            if directory.preset_file(to).exists() {
+                anyhow::bail!(
+                    "Preset '{}' already exists in {}",
+                    to,
+                    directory.presets_dir.display()
+                );
+            }
+
+            // Keep the source format and contents (placeholders included), unless
+            // relative paths have to be rewritten to resolve from the new directory
+            let extension = config_format::ConfigFormat::from_path(&source).extension();
+            let target = directory.presets_dir.join(format!("{}.{}", to, extension));
+            let mut document = config_edit::read_config_document(&source)?;
+            let rewritten = config_manager::rebase_relative_paths(
+                &mut document,
+                &config_manager.preset_path_bases(&source),
+                &config_manager::PathBases::for_preset(directory),
+            );
+            std::fs::create_dir_all(&directory.presets_dir)
+                .with_context(|| format!("Failed to create {}", directory.presets_dir.display()))?;
+            if rewritten.is_empty() {
+                std::fs::copy(&source, &target)
+                    .with_context(|| format!("Failed to copy {}", source.display()))?;
+            } else {
+                config_edit::write_config_document(&target, &document)?;
+                println!(
+                    "Rewrote relative paths in {} to point at the original files",
+                    rewritten.join(", ")
+                );
+            }
+            println!(
+                "✅ Copied preset '{}' ({}) to {}",
+                from,
+                source.display(),
+                target.display()
+            );
+        }
+
+        PresetAction::Rm { name, force, scope } => {
+            let path = config_manager.locate_preset(name, scope.scope())?;
+
+            if !force {
+                print!(
+                    "Are you sure you want to delete preset '{}' ({})? (y/N): ",
+                    name,
+                    path.display()
+                );
+                std::io::Write::flush(&mut std::io::stdout())?;
+
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+
+                if !input.trim().to_lowercase().starts_with('y') {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+            }
+
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete {}", path.display()))?;
+            println!("🗑️  Deleted preset '{}'", name);
+        }
+
+        PresetAction::Diff { a, b } => {
+            let layered_a = config_manager.load_preset(a)?;
+            let layered_b = config_manager.load_preset(b)?;
+            let changes =
+                config_layers::diff_fields(&layered_a.merged.value, &layered_b.merged.value);
+
+            if changes.is_empty() {
+                println!(
+                    "Presets '{}' and '{}' resolve to the same configuration",
+                    a, b
+                );
+                return Ok(());
+            }
+
+            println!("--- {}", a);
+            println!("+++ {}", b);
+            for (path, change) in changes {
+                match change {
+                    config_layers::FieldChange::Changed(old, new) => {
+                        println!("~ {}: {} -> {}", path, old, new)
+                    }
+                    config_layers::FieldChange::Removed(old) => println!("- {}: {}", path, old),
+                    config_layers::FieldChange::Added(new) => println!("+ {}: {}", path, new),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Handle session management commands
 async fn handle_session_command(action: &SessionAction) -> Result<()> {
     let config_manager = ConfigManager::new();
@@ -656,9 +1088,12 @@ async fn handle_session_command(action: &SessionAction) -> Result<()> {
 }
 
 /// Handle management commands
-async fn handle_command(command: &Command) -> Result<()> {
+async fn handle_command(command: &Command, args: &Args) -> Result<()> {
     match command {
-        Command::Init { global } => {
+        Command::Init {
+            global,
+            interactive,
+        } => {
             let directory = if *global {
                 info!("Creating global .th-chat directory");
                 create_global_th_chat_dir()?
@@ -668,60 +1103,439 @@ async fn handle_command(command: &Command) -> Result<()> {
             };
 
             let config_manager = ConfigManager::new();
-            config_manager.create_default_config(&directory)?;
+            let installed = config_manager::detect_installed_mcp_servers();
+            let presets = if *interactive {
+                if !run_init_wizard(&directory, &installed)? {
+                    println!("Configuration not written.");
+                    return Ok(());
+                }
+                Vec::new()
+            } else {
+                config_manager.create_default_config(&directory)?
+            };
+            let gitignore_written = directory.write_gitignore()?;
 
             println!(
                 "✅ Initialized .th-chat directory at: {}",
                 directory.root.display()
             );
             println!("📁 Created directories:");
-            println!("   - config.json (main configuration)");
+            println!(
+                "   - {} (main configuration)",
+                directory
+                    .config_file
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            );
+            if gitignore_written {
+                println!("   - .gitignore (keeps sessions/ out of git)");
+            }
             println!("   - sessions/ (conversation sessions)");
             println!("   - presets/ (configuration presets)");
             println!("   - mcp/ (MCP server configurations)");
-            println!("📄 Created example presets:");
-            println!("   - coding.json");
-            println!("   - research.json");
+            println!("🔌 Detected MCP servers:");
+            if installed.is_empty() {
+                println!("   (none found on $PATH)");
+            }
+            for server in &installed {
+                println!("   - {} ({})", server.command, server.description);
+            }
+            if *interactive {
+                println!("📄 Create presets with 'th-chat presets new <name>'");
+            } else if presets.is_empty() {
+                println!("📄 No example presets created (no known MCP servers installed)");
+            } else {
+                println!("📄 Created example presets:");
+                for preset in &presets {
+                    println!("   - {}.json", preset);
+                }
+            }
             println!();
             println!("🚀 You can now run 'th-chat' to start chatting!");
         }
 
-        Command::Presets => {
+        Command::Presets { action } => {
+            handle_preset_command(action.as_ref().unwrap_or(&PresetAction::List), args)?;
+        }
+
+        Command::Sessions { action } => {
+            handle_session_command(action).await?;
+        }
+
+        Command::Mcp { action } => {
+            handle_mcp_command(action, args)?;
+        }
+
+        Command::Doctor => {
             let config_manager = ConfigManager::new();
-            let presets = config_manager.list_presets()?;
+            let options = doctor::DoctorOptions {
+                server: args.server.clone(),
+                actor_manifest: args.actor_manifest.clone(),
+                load_options: args.config_load_options(),
+            };
+            let results = doctor::run_checks(&config_manager, &options);
+
+            for result in &results {
+                println!(
+                    "{} {}: {}",
+                    result.status.label(),
+                    result.name,
+                    result.detail
+                );
+                if let Some(fix) = &result.fix {
+                    println!("       fix: {}", fix);
+                }
+            }
 
-            if presets.is_empty() {
-                println!("No presets found. Run 'th-chat init' to create example presets.");
+            let failed = results
+                .iter()
+                .filter(|r| r.status == doctor::CheckStatus::Fail)
+                .count();
+            println!();
+            if failed > 0 {
+                anyhow::bail!("{} of {} checks failed", failed, results.len());
+            }
+            let warnings = results
+                .iter()
+                .filter(|r| r.status == doctor::CheckStatus::Warn)
+                .count();
+            println!(
+                "✅ {} checks passed ({} warning(s))",
+                results.len() - warnings,
+                warnings
+            );
+        }
+
+        Command::Config {
+            action: Some(ConfigAction::Validate { file }),
+            ..
+        } => {
+            let config_manager = ConfigManager::new();
+            let files = match file {
+                Some(file) => vec![file.clone()],
+                None => config_manager.config_files(),
+            };
+
+            if files.is_empty() {
+                println!("No config or preset files found.");
+                return Ok(());
+            }
+
+            let mut invalid = 0;
+            for file in &files {
+                let issues = config_schema::validate_config_file(file)?;
+                if issues.is_empty() {
+                    println!("✅ {}", file.display());
+                } else {
+                    invalid += 1;
+                    println!("❌ {}", file.display());
+                    for issue in issues {
+                        println!("   {}:{}", file.display(), issue);
+                    }
+                }
+            }
+
+            // Files hidden by a same-named file in another format are never loaded
+            let shadowed = if file.is_none() {
+                config_manager.shadowed_config_files()
             } else {
-                println!("Available presets:");
-                for (name, source) in presets {
-                    println!("  {} ({})", name, source);
+                Vec::new()
+            };
+            for (ignored, used) in &shadowed {
+                invalid += 1;
+                println!("❌ {}", ignored.display());
+                println!("   ignored: {} is used instead", used.display());
+            }
+
+            if invalid > 0 {
+                anyhow::bail!(
+                    "{} of {} config files are invalid",
+                    invalid,
+                    files.len() + shadowed.len()
+                );
+            }
+        }
+
+        Command::Config {
+            action: Some(ConfigAction::Schema),
+            ..
+        } => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&config_schema::config_schema())?
+            );
+        }
+
+        Command::Config {
+            action:
+                Some(ConfigAction::Convert {
+                    file,
+                    to,
+                    output,
+                    keep,
+                }),
+            ..
+        } => {
+            let from = config_format::ConfigFormat::from_path(file);
+            let content = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            // Placeholders are kept as written; only the syntax changes
+            let value = from
+                .parse(&content)
+                .with_context(|| format!("Failed to parse {}", file.display()))?;
+            let converted = to.serialize(&value)?;
+
+            let output = output
+                .clone()
+                .unwrap_or_else(|| file.with_extension(to.extension()));
+            if output != *file && output.exists() {
+                anyhow::bail!("{} already exists", output.display());
+            }
+            std::fs::write(&output, converted)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            println!(
+                "✅ Converted {} ({}) to {} ({})",
+                file.display(),
+                from,
+                output.display(),
+                to
+            );
+
+            // The original would otherwise take precedence over the converted file
+            if !*keep && output != *file {
+                let mut backup = file.clone().into_os_string();
+                backup.push(".bak");
+                let backup = std::path::PathBuf::from(backup);
+                std::fs::rename(file, &backup)
+                    .with_context(|| format!("Failed to move {}", file.display()))?;
+                println!("   Original moved to {}", backup.display());
+            }
+        }
+
+        Command::Config {
+            preset,
+            action: Some(ConfigAction::Get { path, scope }),
+            ..
+        } => {
+            let config_manager = ConfigManager::new();
+            let segments = config_edit::parse_field_path(path)?;
+
+            // With an explicit scope, read that file as written; otherwise the
+            // resolved configuration
+            let document = match scope.scope() {
+                ConfigScope::Auto => {
+                    let mut options = args.config_load_options();
+                    if preset.is_some() {
+                        options.preset = preset.clone();
+                    }
+                    serde_json::to_value(&config_manager.load_layered(&options)?.config)?
                 }
+                target => {
+                    let file = match preset {
+                        Some(name) => config_manager.locate_preset(name, target)?,
+                        None => config_manager.target_directory(target)?.config_file.clone(),
+                    };
+                    config_edit::read_config_document(&file)?
+                }
+            };
+
+            match config_edit::get_field(&document, &segments) {
+                Some(serde_json::Value::String(text)) => println!("{}", text),
+                Some(value) => println!("{}", serde_json::to_string_pretty(value)?),
+                None => anyhow::bail!("Field '{}' is not set", path),
             }
         }
 
-        Command::Sessions { action } => {
-            handle_session_command(action).await?;
+        Command::Config {
+            preset,
+            action: Some(ConfigAction::Set { path, value, scope }),
+            ..
+        } => {
+            let config_manager = ConfigManager::new();
+            let file = match preset {
+                Some(name) => config_manager.preset_target(name, scope.scope())?,
+                None => config_manager
+                    .target_directory(scope.scope())?
+                    .config_file
+                    .clone(),
+            };
+
+            let segments = config_edit::parse_field_path(path)?;
+            let value = config_edit::parse_cli_value(value);
+            let mut document = config_edit::read_config_document(&file)?;
+            config_edit::set_field(&mut document, &segments, value.clone())?;
+            if config_edit::loses_comments(&file) && !confirm_comment_loss(&file)? {
+                println!(
+                    "{} left unchanged; use `th-chat config edit` to keep comments",
+                    file.display()
+                );
+                return Ok(());
+            }
+            config_edit::write_config_document(&file, &document)?;
+
+            println!("✅ Set {} = {} in {}", path, value, file.display());
         }
 
-        Command::Config { preset } => {
+        Command::Config {
+            preset: outer_preset,
+            action: Some(ConfigAction::Edit { preset, scope }),
+            ..
+        } => {
             let config_manager = ConfigManager::new();
-            let options = ConfigLoadOptions {
-                config_file: None,
-                preset: preset.clone(),
+            let file = match preset.as_ref().or(outer_preset.as_ref()) {
+                Some(name) => config_manager.preset_target(name, scope.scope())?,
+                None => config_manager
+                    .target_directory(scope.scope())?
+                    .config_file
+                    .clone(),
             };
+            edit_config_file(&file)?;
+        }
 
-            let (config, source) = config_manager.load_config(&options)?;
+        Command::Config {
+            preset,
+            explain,
+            action: None,
+        } => {
+            let config_manager = ConfigManager::new();
+            let mut options = args.config_load_options();
+            if preset.is_some() {
+                options.preset = preset.clone();
+            }
 
-            println!("Configuration source: {}", source);
-            println!();
-            println!("{}", serde_json::to_string_pretty(&config)?);
+            let layered = config_manager.load_layered(&options)?;
+
+            if *explain {
+                print_config_explanation(&layered);
+            } else {
+                println!("Configuration source: {}", layered.source());
+                println!();
+                println!("{}", serde_json::to_string_pretty(&layered.config)?);
+            }
         }
     }
 
     Ok(())
 }
 
+/// Edit a config file in $VISUAL/$EDITOR through a temporary copy, so the file is
+/// only replaced once the edited version passes validation
+fn edit_config_file(file: &std::path::Path) -> Result<()> {
+    let format = config_format::ConfigFormat::from_path(file);
+    let original = if file.exists() {
+        std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?
+    } else {
+        format.serialize(&serde_json::json!({}))?
+    };
+
+    // Config files can hold secrets: the scratch copy gets an unpredictable name,
+    // owner-only permissions, and is removed when dropped
+    let mut scratch = tempfile::Builder::new()
+        .prefix("th-chat-edit-")
+        .suffix(&format!(".{}", format.extension()))
+        .tempfile()
+        .context("Failed to create a scratch file for editing")?;
+    io::Write::write_all(scratch.as_file_mut(), original.as_bytes())
+        .with_context(|| format!("Failed to write {}", scratch.path().display()))?;
+
+    let Some(edited) = edit_until_valid(scratch.path(), format)? else {
+        println!("Edit discarded; {} left unchanged", file.display());
+        return Ok(());
+    };
+
+    if edited == original && file.exists() {
+        println!("No changes to {}", file.display());
+        return Ok(());
+    }
+    std::fs::write(file, edited).with_context(|| format!("Failed to write {}", file.display()))?;
+    println!("✅ Saved {}", file.display());
+    Ok(())
+}
+
+/// Warn that `config set` will drop the comments in `file`. Asks to continue when
+/// run interactively; otherwise the warning is printed and the write goes ahead.
+fn confirm_comment_loss(file: &std::path::Path) -> Result<bool> {
+    eprintln!(
+        "⚠️  {} contains comments, which are not kept when the file is rewritten",
+        file.display()
+    );
+    if !io::IsTerminal::is_terminal(&io::stdin()) {
+        return Ok(true);
+    }
+    print!("Write anyway? [y/N] ");
+    io::Write::flush(&mut io::stdout())?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Run the editor until the file validates, returning its contents, or `None` if
+/// the user gives up
+fn edit_until_valid(
+    scratch: &std::path::Path,
+    format: config_format::ConfigFormat,
+) -> Result<Option<String>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("$EDITOR is empty")?;
+    let editor_args: Vec<&str> = parts.collect();
+
+    loop {
+        let status = std::process::Command::new(program)
+            .args(&editor_args)
+            .arg(scratch)
+            .status()
+            .with_context(|| format!("Failed to launch editor '{}'", editor))?;
+        if !status.success() {
+            anyhow::bail!("Editor '{}' exited with {}", editor, status);
+        }
+
+        let edited = std::fs::read_to_string(scratch)
+            .with_context(|| format!("Failed to read {}", scratch.display()))?;
+        let issues = config_schema::validate_config_text(&edited, format);
+        if issues.is_empty() {
+            return Ok(Some(edited));
+        }
+
+        println!("❌ The edited config is invalid:");
+        println!("{}", config_edit::format_issues(&issues));
+        print!("Re-open the editor? [Y/n] ");
+        io::Write::flush(&mut io::stdout())?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim().eq_ignore_ascii_case("n") {
+            return Ok(None);
+        }
+    }
+}
+
+/// Print every configuration field with the layer it came from
+fn print_config_explanation(layered: &config_layers::LayeredConfig) {
+    println!("Configuration layers (lowest priority first):");
+    for (index, layer) in layered.layers.iter().enumerate() {
+        println!("  {}. {}", index + 1, layer.source);
+    }
+    println!();
+
+    for (path, value, source) in layered.explain() {
+        let source = source
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let value = serde_json::to_string(&value).unwrap_or_default();
+        let value = if value.chars().count() > 60 {
+            format!("{}...", value.chars().take(57).collect::<String>())
+        } else {
+            value
+        };
+        println!("{} = {}", path, value);
+        println!("    from {}", source);
+    }
+}
+
 /// Print brief session information
 fn print_session_brief(session: &SessionInfo) {
     let age = format_age(session.age_hours());
@@ -766,8 +1580,65 @@ fn print_session_info(session: &session_manager::SessionData) {
     if let Some(preset) = &session.config_preset {
         println!("   Config preset: {}", preset);
     }
+    if let Some(original) = &session.compacted_from {
+        println!("   Compacted from: {}", original);
+    }
+    if let Some(continuation) = &session.compacted_into {
+        println!("   Continued in: {}", continuation);
+    }
     println!("   Conversation ID: {}", session.conversation_id);
     println!("   Store ID: {}", session.store_id);
+    println!(
+        "   Tokens: {} in, {} out ({} total across {} completions)",
+        session.usage.input_tokens,
+        session.usage.output_tokens,
+        session.usage.total_tokens(),
+        session.usage.completions
+    );
+    if session.usage.estimated_cost > 0.0 {
+        println!("   Estimated cost: ${:.4}", session.usage.estimated_cost);
+    }
+    if !session.tool_calls.is_empty() {
+        let calls: u32 = session.tool_calls.values().map(|counts| counts.calls).sum();
+        let errors: u32 = session
+            .tool_calls
+            .values()
+            .map(|counts| counts.errors)
+            .sum();
+        let blocked: u32 = session
+            .tool_calls
+            .values()
+            .map(|counts| counts.blocked)
+            .sum();
+        if blocked > 0 {
+            println!(
+                "   Tool calls: {} ({} failed, {} blocked)",
+                calls, errors, blocked
+            );
+        } else {
+            println!("   Tool calls: {} ({} failed)", calls, errors);
+        }
+        for (tool, counts) in &session.tool_calls {
+            let failed = if counts.errors > 0 {
+                format!(", {} failed", counts.errors)
+            } else {
+                String::new()
+            };
+            let refused = if counts.blocked > 0 {
+                format!(", {} blocked", counts.blocked)
+            } else {
+                String::new()
+            };
+            println!(
+                "     {}: {}{}{}, {} output",
+                tool,
+                counts.calls,
+                failed,
+                refused,
+                tool_calls::format_size(counts.output_bytes)
+            );
+        }
+    }
 }
 
 /// Format age in hours to human-readable string
@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config_manager::McpConfig;
+
+/// MCP protocol version sent in the `initialize` request
+const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// A named MCP server definition stored in `.th-chat/mcp/<name>.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpDefinition {
+    /// Name the definition is referenced by (the file name, not stored in the file)
+    #[serde(skip)]
+    pub name: String,
+
+    /// Short description shown by `th-chat mcp list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub command: String,
+
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl McpDefinition {
+    /// The `mcp_servers` entry this definition stands for
+    pub fn to_server_value(&self) -> Value {
+        json!({
+            "name": self.name,
+            "config": { "command": self.command, "args": self.args }
+        })
+    }
+}
+
+/// Check that a definition name is a plain file name, so it cannot point outside `mcp/`
+pub fn validate_definition_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) || name.contains("..")
+    {
+        anyhow::bail!(
+            "Invalid MCP server name '{}': names cannot be empty, start with '.' or contain '/', '\\' or '..'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Path of the definition file for `name` in an `mcp/` directory
+pub fn definition_path(mcp_dir: &Path, name: &str) -> Result<PathBuf> {
+    validate_definition_name(name)?;
+    Ok(mcp_dir.join(format!("{}.json", name)))
+}
+
+/// Load a definition file, naming it after the file
+pub fn load_definition(path: &Path) -> Result<McpDefinition> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read MCP server definition: {}", path.display()))?;
+    let mut definition: McpDefinition = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse MCP server definition: {}", path.display()))?;
+    definition.name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(definition)
+}
+
+/// Write a definition file, creating the `mcp/` directory if needed
+pub fn save_definition(mcp_dir: &Path, definition: &McpDefinition) -> Result<PathBuf> {
+    fs::create_dir_all(mcp_dir)
+        .with_context(|| format!("Failed to create mcp directory: {}", mcp_dir.display()))?;
+    let path = definition_path(mcp_dir, &definition.name)?;
+    fs::write(&path, serde_json::to_string_pretty(definition)?)
+        .with_context(|| format!("Failed to write MCP server definition: {}", path.display()))?;
+    Ok(path)
+}
+
+/// Every definition in an `mcp/` directory, sorted by name
+pub fn list_definitions(mcp_dir: &Path) -> Result<Vec<McpDefinition>> {
+    if !mcp_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut definitions = Vec::new();
+    for entry in fs::read_dir(mcp_dir)
+        .with_context(|| format!("Failed to read mcp directory: {}", mcp_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
+            // Files that could not be referenced by name (e.g. hidden files) are skipped
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            if validate_definition_name(&name).is_err() {
+                debug!("Skipping MCP server definition {}", path.display());
+                continue;
+            }
+            definitions.push(load_definition(&path)?);
+        }
+    }
+    definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(definitions)
+}
+
+/// Replace name references (`"mcp_servers": ["fs"]`) with the server they name
+pub fn expand_mcp_references<F>(config: &mut Value, mut lookup: F) -> Result<()>
+where
+    F: FnMut(&str) -> Result<Value>,
+{
+    let Some(servers) = config.get_mut("mcp_servers").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    for (index, server) in servers.iter_mut().enumerate() {
+        if let Value::String(name) = server {
+            debug!("Expanding MCP server reference '{}'", name);
+            *server = lookup(name).with_context(|| format!("mcp_servers[{}]", index))?;
+        }
+    }
+    Ok(())
+}
+
+/// A tool reported by an MCP server
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpToolInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// JSON Schema of the tool's arguments
+    pub input_schema: Option<Value>,
+}
+
+impl McpToolInfo {
+    /// Read a tool in the MCP wire format (`name`, `description`, `inputSchema`)
+    pub fn from_value(tool: &Value) -> Option<Self> {
+        Some(Self {
+            name: tool.get("name")?.as_str()?.to_string(),
+            description: tool
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            input_schema: tool.get("inputSchema").cloned(),
+        })
+    }
+}
+
+/// Launch an MCP server over stdio, perform the `initialize` handshake and list
+/// its tools
+pub fn probe_server(config: &McpConfig, timeout: Duration) -> Result<Vec<McpToolInfo>> {
+    let mut child = Command::new(&config.command)
+        .args(&config.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", config.command))?;
+
+    let result = run_probe(&mut child, timeout);
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+fn run_probe(child: &mut Child, timeout: Duration) -> Result<Vec<McpToolInfo>> {
+    let mut stdin = child.stdin.take().context("MCP server stdin unavailable")?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("MCP server stdout unavailable")?;
+
+    // Read responses on a thread so a silent server cannot block us past the timeout
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let deadline = Instant::now() + timeout;
+
+    send(
+        &mut stdin,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "th-chat", "version": env!("CARGO_PKG_VERSION") }
+            }
+        }),
+    )?;
+    wait_for_response(&receiver, 1, deadline).context("initialize failed")?;
+
+    send(
+        &mut stdin,
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    )?;
+    send(
+        &mut stdin,
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list", "params": {} }),
+    )?;
+    let result = wait_for_response(&receiver, 2, deadline).context("tools/list failed")?;
+
+    Ok(parse_tools(&result))
+}
+
+fn send(stdin: &mut impl Write, message: Value) -> Result<()> {
+    writeln!(stdin, "{}", message).context("Failed to write to MCP server")?;
+    stdin.flush().context("Failed to write to MCP server")
+}
+
+/// Wait for the response with `id`, skipping notifications and non-JSON output
+fn wait_for_response(
+    receiver: &mpsc::Receiver<String>,
+    id: u64,
+    deadline: Instant,
+) -> Result<Value> {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let line = match receiver.recv_timeout(remaining) {
+            Ok(line) => line,
+            Err(mpsc::RecvTimeoutError::Timeout) => anyhow::bail!("timed out waiting for response"),
+            Err(mpsc::RecvTimeoutError::Disconnected) => anyhow::bail!("server exited"),
+        };
+
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            debug!("Ignoring non-JSON MCP output: {}", line);
+            continue;
+        };
+        if message.get("id").and_then(Value::as_u64) != Some(id) {
+            continue;
+        }
+        if let Some(error) = message.get("error") {
+            anyhow::bail!("server returned an error: {}", error);
+        }
+        return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+    }
+}
+
+/// Tools from a `tools/list` result
+pub fn parse_tools(result: &Value) -> Vec<McpToolInfo> {
+    result
+        .get("tools")
+        .and_then(Value::as_array)
+        .map(|tools| tools.iter().filter_map(McpToolInfo::from_value).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_definition_names() {
+        let mcp_dir = Path::new("/project/.th-chat/mcp");
+        assert_eq!(
+            definition_path(mcp_dir, "fs").unwrap(),
+            mcp_dir.join("fs.json")
+        );
+        for name in ["", "../x", "../../foo", "a/b", "a\\b", ".hidden", "x..y"] {
+            assert!(definition_path(mcp_dir, name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_definitions_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mcp_dir = temp_dir.path().join("mcp");
+
+        let definition = McpDefinition {
+            name: "fs".to_string(),
+            description: Some("Filesystem".to_string()),
+            command: "fs-mcp-server".to_string(),
+            args: vec!["--allowed-dirs".to_string(), "${PWD}".to_string()],
+        };
+        save_definition(&mcp_dir, &definition).unwrap();
+
+        let definitions = list_definitions(&mcp_dir).unwrap();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].name, "fs");
+        assert_eq!(definitions[0].args, definition.args);
+        assert_eq!(
+            definitions[0].to_server_value(),
+            json!({
+                "name": "fs",
+                "config": { "command": "fs-mcp-server", "args": ["--allowed-dirs", "${PWD}"] }
+            })
+        );
+    }
+
+    #[test]
+    fn test_expand_mcp_references() {
+        let mut config = json!({
+            "mcp_servers": ["fs", { "config": { "command": "other", "args": [] } }]
+        });
+        expand_mcp_references(&mut config, |name| {
+            Ok(json!({ "name": name, "config": { "command": "fs-mcp-server", "args": [] } }))
+        })
+        .unwrap();
+        assert_eq!(config["mcp_servers"][0]["name"], "fs");
+        assert_eq!(config["mcp_servers"][1]["config"]["command"], "other");
+
+        let mut config = json!({ "mcp_servers": ["missing"] });
+        let error = expand_mcp_references(&mut config, |_| anyhow::bail!("not found")).unwrap_err();
+        assert!(format!("{:#}", error).contains("mcp_servers[0]"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_probe_server() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("fake-mcp-server");
+        fs::write(
+            &script,
+            r#"#!/bin/sh
+read line
+echo 'starting up'
+echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","capabilities":{}}}'
+read line
+read line
+echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"read_file","description":"Read a file"},{"name":"list"}]}}'
+"#,
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = McpConfig {
+            command: script.to_string_lossy().to_string(),
+            args: vec![],
+        };
+        let tools = probe_server(&config, Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            tools,
+            vec![
+                McpToolInfo {
+                    name: "read_file".to_string(),
+                    description: Some("Read a file".to_string()),
+                    input_schema: None,
+                },
+                McpToolInfo {
+                    name: "list".to_string(),
+                    description: None,
+                    input_schema: None,
+                },
+            ]
+        );
+    }
+}
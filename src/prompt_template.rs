@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Maximum nesting of `{{include ...}}` tags, to stop include cycles
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Variables available to system prompt templates
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    variables: HashMap<String, String>,
+}
+
+impl TemplateContext {
+    /// Create a context from explicit variables
+    pub fn new(variables: HashMap<String, String>) -> Self {
+        Self { variables }
+    }
+
+    /// Gather `cwd`, `git_branch`, `date` and `project_name` for the current directory
+    pub fn from_environment() -> Result<Self> {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        let git_root = git_output(&cwd, &["rev-parse", "--show-toplevel"]).map(PathBuf::from);
+        let project_root = git_root.as_deref().unwrap_or(&cwd);
+
+        let mut variables = HashMap::new();
+        variables.insert("cwd".to_string(), cwd.to_string_lossy().to_string());
+        variables.insert(
+            "git_branch".to_string(),
+            git_output(&cwd, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default(),
+        );
+        variables.insert("date".to_string(), today());
+        variables.insert(
+            "project_name".to_string(),
+            project_root
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        );
+
+        debug!("Prompt template variables: {:?}", variables);
+        Ok(Self { variables })
+    }
+}
+
+/// Render a prompt template.
+///
+/// `{{name}}` is replaced by a context variable and `{{include path}}` by the rendered
+/// contents of a file, resolved relative to `base_dir`. Unknown tags are left as-is so
+/// prompts that happen to contain `{{` keep working.
+pub fn render(template: &str, context: &TemplateContext, base_dir: &Path) -> Result<String> {
+    render_at_depth(template, context, base_dir, 0)
+}
+
+fn render_at_depth(
+    template: &str,
+    context: &TemplateContext,
+    base_dir: &Path,
+    depth: usize,
+) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            output.push_str(&rest[start..]);
+            return Ok(output);
+        };
+
+        let tag = after[..end].trim();
+        if let Some(path) = tag.strip_prefix("include ") {
+            output.push_str(&render_include(path.trim(), context, base_dir, depth)?);
+        } else if let Some(value) = context.variables.get(tag) {
+            output.push_str(value);
+        } else {
+            output.push_str(&rest[start..start + 2 + end + 2]);
+        }
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn render_include(
+    path: &str,
+    context: &TemplateContext,
+    base_dir: &Path,
+    depth: usize,
+) -> Result<String> {
+    if depth >= MAX_INCLUDE_DEPTH {
+        anyhow::bail!(
+            "Prompt includes nested more than {} levels deep (include cycle?)",
+            MAX_INCLUDE_DEPTH
+        );
+    }
+
+    let path = resolve_prompt_path(path, base_dir);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to include prompt file: {}", path.display()))?;
+    let include_dir = path.parent().unwrap_or(base_dir);
+    render_at_depth(&content, context, include_dir, depth + 1)
+}
+
+/// Resolve a prompt file path, expanding `~/` and paths relative to `base_dir`
+pub fn resolve_prompt_path(path: &str, base_dir: &Path) -> PathBuf {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(stripped);
+        }
+    }
+    base_dir.join(path)
+}
+
+/// Trimmed stdout of a git command, if it succeeds
+fn git_output(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Today's date (UTC) as YYYY-MM-DD
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context() -> TemplateContext {
+        let mut variables = HashMap::new();
+        variables.insert("project_name".to_string(), "th-chat".to_string());
+        variables.insert("git_branch".to_string(), "main".to_string());
+        TemplateContext::new(variables)
+    }
+
+    #[test]
+    fn test_render_variables_and_includes() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("prompts")).unwrap();
+        fs::write(
+            temp_dir.path().join("prompts").join("rules.md"),
+            "Branch: {{ git_branch }}\n{{include footer.md}}",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("prompts").join("footer.md"),
+            "Be brief.",
+        )
+        .unwrap();
+
+        let rendered = render(
+            "Project {{project_name}}.\n{{include prompts/rules.md}}\nKeep {{unknown}} and {{ open",
+            &context(),
+            temp_dir.path(),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "Project th-chat.\nBranch: main\nBe brief.\nKeep {{unknown}} and {{ open"
+        );
+
+        assert!(render("{{include missing.md}}", &context(), temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.md"), "{{include a.md}}").unwrap();
+        assert!(render("{{include a.md}}", &context(), temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
@@ -1,9 +1,12 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use tracing::{debug, info, warn};
 
+use crate::tool_calls::ToolCallCounts;
+
 /// Enhanced session data with metadata and naming support
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionData {
@@ -15,6 +18,77 @@ pub struct SessionData {
     pub description: Option<String>,
     pub config_preset: Option<String>,
     pub message_count: u32,
+    #[serde(default)]
+    pub usage: SessionUsage,
+    /// Session this one was compacted from (its summary seeds this conversation)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compacted_from: Option<String>,
+    /// Session created when this one was compacted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compacted_into: Option<String>,
+    /// Approvals and denials of tool calls matching `confirm_tools`, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_decisions: Vec<ToolDecision>,
+    /// Tools the user chose to always allow for the rest of the session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub always_allowed_tools: Vec<String>,
+    /// Tool calls in the conversation, by tool name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_calls: BTreeMap<String, ToolCallCounts>,
+}
+
+/// How a tool call that needed approval was decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    Approved,
+    Denied,
+    /// Approved, and the tool is approved automatically from now on
+    AlwaysAllowed,
+    /// Approved without asking because the tool was always allowed earlier
+    AutoApproved,
+}
+
+impl ApprovalDecision {
+    pub fn is_approved(&self) -> bool {
+        !matches!(self, ApprovalDecision::Denied)
+    }
+}
+
+/// A logged approval decision for a tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDecision {
+    pub tool_use_id: String,
+    pub tool: String,
+    pub input: serde_json::Value,
+    pub decision: ApprovalDecision,
+    pub decided_at: u64,
+}
+
+/// Cumulative token usage for a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub completions: u32,
+    /// Estimated cost in USD, only counting completions from priced models
+    pub estimated_cost: f64,
+}
+
+impl SessionUsage {
+    /// Record the usage of a single completion
+    pub fn record(&mut self, input_tokens: u64, output_tokens: u64, cost: Option<f64>) {
+        self.input_tokens += input_tokens;
+        self.output_tokens += output_tokens;
+        self.completions += 1;
+        if let Some(cost) = cost {
+            self.estimated_cost += cost;
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
 }
 
 impl SessionData {
@@ -33,6 +107,12 @@ impl SessionData {
             description: None,
             config_preset: None,
             message_count: 0,
+            usage: SessionUsage::default(),
+            compacted_from: None,
+            compacted_into: None,
+            tool_decisions: Vec::new(),
+            always_allowed_tools: Vec::new(),
+            tool_calls: BTreeMap::new(),
         }
     }
 
@@ -53,6 +133,30 @@ impl SessionData {
             .as_secs();
     }
 
+    /// Log a tool approval decision, remembering tools that are now always allowed
+    pub fn record_tool_decision(
+        &mut self,
+        tool_use_id: String,
+        tool: String,
+        input: serde_json::Value,
+        decision: ApprovalDecision,
+    ) {
+        if decision == ApprovalDecision::AlwaysAllowed && !self.always_allowed_tools.contains(&tool)
+        {
+            self.always_allowed_tools.push(tool.clone());
+        }
+        self.tool_decisions.push(ToolDecision {
+            tool_use_id,
+            tool,
+            input,
+            decision,
+            decided_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        });
+    }
+
     /// Convert to persistence::SessionData for compatibility with ChatManager
     pub fn to_persistence_session_data(&self) -> crate::persistence::SessionData {
         crate::persistence::SessionData {
@@ -359,6 +463,12 @@ impl SessionManager {
             description: Some("Migrated from legacy session".to_string()),
             config_preset: None,
             message_count: 0, // We don't know the count from legacy
+            usage: SessionUsage::default(),
+            compacted_from: None,
+            compacted_into: None,
+            tool_decisions: Vec::new(),
+            always_allowed_tools: Vec::new(),
+            tool_calls: BTreeMap::new(),
         };
 
         // Save as new format
@@ -380,6 +490,18 @@ impl SessionManager {
         format!("session-{}", next_number)
     }
 
+    /// Find a free name for the session created when compacting `base_name`
+    pub fn next_compacted_session_name(&self, base_name: &str) -> String {
+        let mut number = 1;
+        loop {
+            let candidate = format!("{}-compact-{}", base_name, number);
+            if !self.session_exists(&candidate) {
+                return candidate;
+            }
+            number += 1;
+        }
+    }
+
     /// Count the number of session files in the sessions directory
     fn count_session_files(&self) -> Result<usize> {
         let entries = std::fs::read_dir(&self.sessions_dir)
@@ -497,4 +619,76 @@ mod tests {
         assert_eq!(default_session.conversation_id, "legacy-conv");
         assert_eq!(default_session.store_id, "legacy-store");
     }
+
+    #[test]
+    fn test_next_compacted_session_name() {
+        let temp_dir = tempdir().unwrap();
+        let manager = SessionManager::new(temp_dir.path().join("sessions")).unwrap();
+
+        assert_eq!(manager.next_compacted_session_name("work"), "work-compact-1");
+
+        manager
+            .create_session("work-compact-1", "c".to_string(), "s".to_string(), None, None)
+            .unwrap();
+        assert_eq!(manager.next_compacted_session_name("work"), "work-compact-2");
+    }
+
+    #[test]
+    fn test_session_usage_persistence() {
+        let temp_dir = tempdir().unwrap();
+        let manager = SessionManager::new(temp_dir.path().join("sessions")).unwrap();
+
+        let mut session = SessionData::new(
+            "usage".to_string(),
+            "conv-1".to_string(),
+            "store-1".to_string(),
+        );
+        session.usage.record(1_000, 200, Some(0.01));
+        session.usage.record(1_500, 300, None);
+        manager.save_session(&session).unwrap();
+
+        let loaded = manager.load_session("usage").unwrap();
+        assert_eq!(loaded.usage.input_tokens, 2_500);
+        assert_eq!(loaded.usage.output_tokens, 500);
+        assert_eq!(loaded.usage.completions, 2);
+        assert!((loaded.usage.estimated_cost - 0.01).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_tool_decision_log() {
+        let temp_dir = tempdir().unwrap();
+        let manager = SessionManager::new(temp_dir.path().join("sessions")).unwrap();
+
+        let mut session = SessionData::new(
+            "approvals".to_string(),
+            "conv-1".to_string(),
+            "store-1".to_string(),
+        );
+        let input = serde_json::json!({ "path": "src/main.rs" });
+        session.record_tool_decision(
+            "tool-1".to_string(),
+            "delete_file".to_string(),
+            input.clone(),
+            ApprovalDecision::Denied,
+        );
+        session.record_tool_decision(
+            "tool-2".to_string(),
+            "write_file".to_string(),
+            input.clone(),
+            ApprovalDecision::AlwaysAllowed,
+        );
+        session.record_tool_decision(
+            "tool-3".to_string(),
+            "write_file".to_string(),
+            input,
+            ApprovalDecision::AutoApproved,
+        );
+        manager.save_session(&session).unwrap();
+
+        let loaded = manager.load_session("approvals").unwrap();
+        assert_eq!(loaded.tool_decisions.len(), 3);
+        assert_eq!(loaded.tool_decisions[0].decision, ApprovalDecision::Denied);
+        assert!(!loaded.tool_decisions[0].decision.is_approved());
+        assert_eq!(loaded.always_allowed_tools, vec!["write_file".to_string()]);
+    }
 }
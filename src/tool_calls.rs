@@ -0,0 +1,390 @@
+use genai_types::{Message, MessageContent};
+use mcp_protocol::tool::ToolContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Where a tool call is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCallStatus {
+    /// No result yet
+    Pending,
+    Succeeded,
+    Failed,
+    /// Failed for a tool the config blocks with `allowed_tools`/`denied_tools`,
+    /// i.e. refused by the actor
+    Blocked,
+}
+
+/// The result matched to a tool call
+#[derive(Debug, Clone)]
+pub struct ToolCallResult {
+    pub content: Vec<ToolContent>,
+    pub is_error: bool,
+}
+
+impl ToolCallResult {
+    /// The text of the result, joined line by line
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                ToolContent::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Bytes of output: text length, plus the encoded size of images and audio
+    pub fn output_size(&self) -> usize {
+        self.content
+            .iter()
+            .map(|content| match content {
+                ToolContent::Text { text } => text.len(),
+                ToolContent::Image { data, .. } | ToolContent::Audio { data, .. } => data.len(),
+                ToolContent::Resource { resource } => resource.to_string().len(),
+            })
+            .sum()
+    }
+}
+
+/// A tool use paired with its result, matched by the tool use ID
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    /// Index of the message holding the tool use
+    pub message_index: usize,
+    pub result: Option<ToolCallResult>,
+    /// Why the config blocks this call, set by `mark_blocked_calls` when it failed
+    pub blocked: Option<String>,
+}
+
+impl ToolCall {
+    pub fn status(&self) -> ToolCallStatus {
+        match &self.result {
+            None => ToolCallStatus::Pending,
+            Some(result) if result.is_error && self.blocked.is_some() => ToolCallStatus::Blocked,
+            Some(result) if result.is_error => ToolCallStatus::Failed,
+            Some(_) => ToolCallStatus::Succeeded,
+        }
+    }
+
+    /// What the call acted on, taken from the first input field naming a path, URL,
+    /// command or query
+    pub fn target(&self) -> Option<&str> {
+        TARGET_KEYS
+            .iter()
+            .find_map(|key| self.input.get(*key)?.as_str())
+    }
+}
+
+/// Input keys tried in order for a tool call's target
+const TARGET_KEYS: &[&str] = &[
+    "path",
+    "file_path",
+    "filePath",
+    "file",
+    "directory",
+    "url",
+    "uri",
+    "command",
+    "query",
+    "pattern",
+    "name",
+];
+
+/// Calls of one tool in a conversation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallCounts {
+    pub calls: u32,
+    /// Failed calls, not counting blocked ones
+    pub errors: u32,
+    /// Calls refused because the config blocks the tool
+    #[serde(default)]
+    pub blocked: u32,
+    /// Bytes of output returned over all calls
+    pub output_bytes: u64,
+}
+
+/// Per-tool counts for a list of tool calls, by tool name
+pub fn count_tool_calls(calls: &[ToolCall]) -> BTreeMap<String, ToolCallCounts> {
+    let mut counts: BTreeMap<String, ToolCallCounts> = BTreeMap::new();
+    for call in calls {
+        let entry = counts.entry(call.name.clone()).or_default();
+        entry.calls += 1;
+        match call.status() {
+            ToolCallStatus::Failed => entry.errors += 1,
+            ToolCallStatus::Blocked => entry.blocked += 1,
+            ToolCallStatus::Pending | ToolCallStatus::Succeeded => {}
+        }
+        if let Some(result) = &call.result {
+            entry.output_bytes += result.output_size() as u64;
+        }
+    }
+    counts
+}
+
+/// Mark the failed calls of tools the config blocks, which the actor refused rather
+/// than the tool failing. `check` gives the config's reason for blocking a tool.
+pub fn mark_blocked_calls<F>(calls: &mut [ToolCall], check: F)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    for call in calls {
+        if call.result.as_ref().map_or(false, |result| result.is_error) {
+            call.blocked = check(&call.name);
+        }
+    }
+}
+
+/// Every tool use in `messages`, in order, with the result answering it if there
+/// is one yet. Results whose tool use is not in `messages` are left out.
+pub fn pair_tool_calls(messages: &[Message]) -> Vec<ToolCall> {
+    let mut calls: Vec<ToolCall> = Vec::new();
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+
+    for (message_index, message) in messages.iter().enumerate() {
+        for content in &message.content {
+            match content {
+                MessageContent::ToolUse { id, name, input } => {
+                    by_id.insert(id.clone(), calls.len());
+                    calls.push(ToolCall {
+                        id: id.clone(),
+                        name: name.clone(),
+                        input: input.clone(),
+                        message_index,
+                        result: None,
+                        blocked: None,
+                    });
+                }
+                MessageContent::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    let Some(&call) = by_id.get(tool_use_id) else {
+                        continue;
+                    };
+                    if calls[call].result.is_none() {
+                        calls[call].result = Some(ToolCallResult {
+                            content: content.clone(),
+                            is_error: is_error.unwrap_or(false),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    calls
+}
+
+/// When tool calls started and how long they took, for calls seen live. Messages
+/// loaded from history carry no timing, so their calls have no duration.
+#[derive(Debug, Default)]
+pub struct ToolTimings {
+    started: HashMap<String, Instant>,
+    durations: HashMap<String, Duration>,
+}
+
+impl ToolTimings {
+    /// Note the tool uses and results in a message as it arrives
+    pub fn observe(&mut self, message: &Message) {
+        let now = Instant::now();
+        for content in &message.content {
+            match content {
+                MessageContent::ToolUse { id, .. } => {
+                    self.started.entry(id.clone()).or_insert(now);
+                }
+                MessageContent::ToolResult { tool_use_id, .. } => {
+                    if let Some(started) = self.started.get(tool_use_id) {
+                        self.durations
+                            .entry(tool_use_id.clone())
+                            .or_insert_with(|| now.duration_since(*started));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// How long a finished call took, or how long an unfinished one has been running
+    pub fn duration(&self, id: &str) -> Option<Duration> {
+        self.durations
+            .get(id)
+            .copied()
+            .or_else(|| self.started.get(id).map(Instant::elapsed))
+    }
+}
+
+/// Byte count for display, e.g. `512 B`, `3.2 KB` or `1.4 MB`
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1_024 {
+        format!("{} B", bytes)
+    } else if bytes < 1_024 * 1_024 {
+        format!("{:.1} KB", bytes as f64 / 1_024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1_024.0 * 1_024.0))
+    }
+}
+
+/// Short duration for the chat view, e.g. `850ms`, `2.4s` or `1m 05s`
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1_000 {
+        format!("{}ms", millis)
+    } else if millis < 60_000 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        let secs = duration.as_secs();
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genai_types::messages::Role;
+    use serde_json::json;
+
+    fn tool_use(id: &str, name: &str) -> MessageContent {
+        MessageContent::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: json!({ "path": "src/main.rs" }),
+        }
+    }
+
+    fn tool_result(id: &str, is_error: bool) -> MessageContent {
+        MessageContent::ToolResult {
+            tool_use_id: id.to_string(),
+            content: vec![ToolContent::Text {
+                text: "ok".to_string(),
+            }],
+            is_error: Some(is_error),
+        }
+    }
+
+    fn message(role: Role, content: Vec<MessageContent>) -> Message {
+        Message { role, content }
+    }
+
+    #[test]
+    fn test_pair_tool_calls() {
+        let messages = vec![
+            message(
+                Role::Assistant,
+                vec![tool_use("a", "read_file"), tool_use("b", "write_file")],
+            ),
+            message(
+                Role::User,
+                vec![tool_result("b", true), tool_result("orphan", false)],
+            ),
+            message(Role::Assistant, vec![tool_use("c", "list_directory")]),
+        ];
+
+        let calls = pair_tool_calls(&messages);
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].status(), ToolCallStatus::Pending);
+        assert_eq!(calls[1].status(), ToolCallStatus::Failed);
+        assert_eq!(calls[2].message_index, 2);
+        assert_eq!(calls[0].target(), Some("src/main.rs"));
+
+        let counts = count_tool_calls(&calls);
+        assert_eq!(
+            counts["write_file"],
+            ToolCallCounts {
+                calls: 1,
+                errors: 1,
+                blocked: 0,
+                output_bytes: 2,
+            }
+        );
+        assert_eq!(counts["read_file"].errors, 0);
+    }
+
+    #[test]
+    fn test_blocked_tool_call() {
+        let refusal = MessageContent::ToolResult {
+            tool_use_id: "a".to_string(),
+            content: vec![ToolContent::Text {
+                text: "Tool 'delete_file' matches denied_tools 'delete_*'".to_string(),
+            }],
+            is_error: Some(true),
+        };
+        let messages = vec![
+            message(
+                Role::Assistant,
+                vec![
+                    tool_use("a", "delete_file"),
+                    tool_use("b", "read_file"),
+                    tool_use("c", "delete_dir"),
+                ],
+            ),
+            message(
+                Role::User,
+                vec![
+                    refusal,
+                    MessageContent::ToolResult {
+                        tool_use_id: "b".to_string(),
+                        content: vec![ToolContent::Text {
+                            text: "config.toml: unknown key denied_tools".to_string(),
+                        }],
+                        is_error: Some(true),
+                    },
+                    tool_result("c", false),
+                ],
+            ),
+        ];
+
+        let mut calls = pair_tool_calls(&messages);
+        mark_blocked_calls(&mut calls, |tool| {
+            tool.starts_with("delete_")
+                .then(|| "session matches denied_tools 'delete_*'".to_string())
+        });
+        assert_eq!(calls[0].status(), ToolCallStatus::Blocked);
+        // Errors of tools the config allows are failures of the tool itself, whatever
+        // the error says, and a blocked tool that succeeded was not refused
+        assert_eq!(calls[1].status(), ToolCallStatus::Failed);
+        assert_eq!(calls[2].status(), ToolCallStatus::Succeeded);
+
+        let counts = count_tool_calls(&calls);
+        assert_eq!(counts["delete_file"].blocked, 1);
+        assert_eq!(counts["delete_file"].errors, 0);
+        assert_eq!(counts["read_file"].errors, 1);
+    }
+
+    #[test]
+    fn test_tool_timings() {
+        let mut timings = ToolTimings::default();
+        timings.observe(&message(Role::Assistant, vec![tool_use("a", "read_file")]));
+        assert!(timings.duration("a").is_some());
+
+        timings.observe(&message(Role::User, vec![tool_result("a", false)]));
+        let finished = timings.duration("a").unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(timings.duration("a"), Some(finished));
+
+        // Results for calls that were never seen starting have no duration
+        timings.observe(&message(Role::User, vec![tool_result("b", false)]));
+        assert_eq!(timings.duration("b"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(2_460)), "2.5s");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1m 05s");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(3_277), "3.2 KB");
+        assert_eq!(format_size(1_468_006), "1.4 MB");
+    }
+}
@@ -0,0 +1,231 @@
+use serde_json::Value;
+
+use crate::config_manager::McpServer;
+use crate::mcp_registry::McpToolInfo;
+
+/// What the chat-state actor made of a configured MCP server
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerState {
+    /// Started by the actor, which reported its tools
+    Loaded,
+    /// Known to the actor but no tools were reported
+    NoTools,
+    /// Not sent to the actor, with the reason (e.g. command not found)
+    Skipped(String),
+    /// Sent to the actor but missing from the settings it reported
+    Missing,
+    /// The actor's settings could not be read
+    Unknown,
+}
+
+impl ServerState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServerState::Loaded => "loaded",
+            ServerState::NoTools => "no tools",
+            ServerState::Skipped(_) => "skipped",
+            ServerState::Missing => "not loaded",
+            ServerState::Unknown => "unknown",
+        }
+    }
+}
+
+/// A configured MCP server and the tools the actor reported for it
+#[derive(Debug, Clone)]
+pub struct ServerInventory {
+    pub name: String,
+    pub command: String,
+    pub state: ServerState,
+    pub tools: Vec<McpToolInfo>,
+}
+
+/// Every configured MCP server with its status and tools
+#[derive(Debug, Clone, Default)]
+pub struct ToolInventory {
+    pub servers: Vec<ServerInventory>,
+    /// Why the actor's settings could not be read, if they could not
+    pub error: Option<String>,
+}
+
+impl ToolInventory {
+    /// Match the servers sent to the actor (and those skipped before sending) with
+    /// the servers the actor reports after `update_settings`. `reported` is `None`
+    /// when the actor's settings could not be read.
+    pub fn build(
+        sent: &[McpServer],
+        skipped: &[(String, String)],
+        reported: Option<&[McpServer]>,
+    ) -> Self {
+        let mut servers: Vec<ServerInventory> = sent
+            .iter()
+            .map(|server| {
+                let name = server.display_name();
+                let actor_server = reported
+                    .and_then(|reported| reported.iter().find(|r| r.display_name() == name));
+                let tools: Vec<McpToolInfo> = actor_server
+                    .and_then(|r| r.tools.as_ref())
+                    .map(|tools| {
+                        tools
+                            .iter()
+                            .filter_map(|tool| {
+                                McpToolInfo::from_value(&serde_json::to_value(tool).ok()?)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let state = match (reported, actor_server) {
+                    (None, _) => ServerState::Unknown,
+                    (Some(_), None) => ServerState::Missing,
+                    (Some(_), Some(_)) if tools.is_empty() => ServerState::NoTools,
+                    (Some(_), Some(_)) => ServerState::Loaded,
+                };
+                ServerInventory {
+                    name,
+                    command: server.config.command.clone(),
+                    state,
+                    tools,
+                }
+            })
+            .collect();
+
+        servers.extend(skipped.iter().map(|(name, reason)| ServerInventory {
+            name: name.clone(),
+            command: name.clone(),
+            state: ServerState::Skipped(reason.clone()),
+            tools: Vec::new(),
+        }));
+
+        Self {
+            servers,
+            error: None,
+        }
+    }
+
+    /// Inventory for when the actor could not be asked: every server is `Unknown`
+    pub fn unavailable(sent: &[McpServer], skipped: &[(String, String)], error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::build(sent, skipped, None)
+        }
+    }
+
+    pub fn tool_count(&self) -> usize {
+        self.servers.iter().map(|server| server.tools.len()).sum()
+    }
+
+    /// Names of the servers that reported a tool called `tool`
+    pub fn servers_with_tool<'a>(&'a self, tool: &'a str) -> impl Iterator<Item = &'a str> {
+        self.servers
+            .iter()
+            .filter(move |server| server.tools.iter().any(|t| t.name == tool))
+            .map(|server| server.name.as_str())
+    }
+}
+
+/// One-line summary of a tool's arguments from its input schema, e.g.
+/// `path: string, recursive?: boolean`
+pub fn schema_summary(schema: &Value) -> Option<String> {
+    let properties = schema.get("properties")?.as_object()?;
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|fields| fields.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let fields: Vec<String> = properties
+        .iter()
+        .map(|(name, property)| {
+            let optional = if required.contains(&name.as_str()) {
+                ""
+            } else {
+                "?"
+            };
+            let kind = property
+                .get("type")
+                .map(|kind| match kind {
+                    Value::String(kind) => kind.clone(),
+                    other => other.to_string(),
+                })
+                .unwrap_or_else(|| "any".to_string());
+            format!("{}{}: {}", name, optional, kind)
+        })
+        .collect();
+    Some(fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_manager::McpConfig;
+    use serde_json::json;
+
+    fn server(command: &str, tools: Option<Value>) -> McpServer {
+        McpServer {
+            name: None,
+            actor_id: None,
+            config: McpConfig {
+                command: command.to_string(),
+                args: vec![],
+            },
+            tools: tools.map(|tools| serde_json::from_value(tools).unwrap()),
+            allowed_tools: None,
+            denied_tools: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_inventory() {
+        let sent = vec![
+            server("/usr/bin/fs-mcp-server", None),
+            server("/usr/bin/git-mcp-server", None),
+        ];
+        let skipped = vec![(
+            "missing-server".to_string(),
+            "MCP server command not found: missing-server".to_string(),
+        )];
+        let reported = vec![server(
+            "/usr/bin/fs-mcp-server",
+            Some(json!([{
+                "name": "read_file",
+                "description": "Read a file",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "path": { "type": "string" } },
+                    "required": ["path"]
+                }
+            }])),
+        )];
+
+        let inventory = ToolInventory::build(&sent, &skipped, Some(reported.as_slice()));
+        assert_eq!(inventory.servers.len(), 3);
+        assert_eq!(inventory.servers[0].name, "fs-mcp-server");
+        assert_eq!(inventory.servers[0].state, ServerState::Loaded);
+        assert_eq!(inventory.servers[0].tools[0].name, "read_file");
+        assert_eq!(inventory.servers[1].state, ServerState::Missing);
+        assert!(matches!(
+            inventory.servers[2].state,
+            ServerState::Skipped(_)
+        ));
+        assert_eq!(inventory.tool_count(), 1);
+
+        let unavailable = ToolInventory::unavailable(&sent, &[], "timeout".to_string());
+        assert_eq!(unavailable.servers[0].state, ServerState::Unknown);
+    }
+
+    #[test]
+    fn test_schema_summary() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "recursive": { "type": "boolean" }
+            },
+            "required": ["path"]
+        });
+        assert_eq!(
+            schema_summary(&schema).as_deref(),
+            Some("path: string, recursive?: boolean")
+        );
+        assert_eq!(schema_summary(&json!({})), None);
+    }
+}
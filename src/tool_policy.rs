@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use crate::config_manager::ConversationConfig;
+
+/// Match a tool name against a glob where `*` matches any run of characters and
+/// `?` matches exactly one
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it is currently matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// `allowed_tools` / `denied_tools` globs from one config level
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolFilter {
+    /// Only tools matching one of these may be used; `None` allows every tool
+    pub allowed: Option<Vec<String>>,
+    pub denied: Vec<String>,
+}
+
+impl ToolFilter {
+    pub fn new(allowed: Option<&[String]>, denied: &[String]) -> Self {
+        Self {
+            allowed: allowed.map(<[String]>::to_vec),
+            denied: denied.to_vec(),
+        }
+    }
+
+    /// Why `tool` is blocked, or `None` if it may be used. Denials win over allows.
+    pub fn check(&self, tool: &str) -> Option<String> {
+        if let Some(pattern) = self.denied.iter().find(|p| glob_match(p, tool)) {
+            return Some(format!("matches denied_tools '{}'", pattern));
+        }
+        match &self.allowed {
+            Some(allowed) if !allowed.iter().any(|p| glob_match(p, tool)) => {
+                Some("not in allowed_tools".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Session- and server-level tool filters, used by `/tools` to mark the tools the
+/// configuration blocks
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicy {
+    session: ToolFilter,
+    /// Filters of MCP servers, by server name
+    servers: HashMap<String, ToolFilter>,
+}
+
+impl ToolPolicy {
+    pub fn from_config(config: &ConversationConfig) -> Self {
+        Self {
+            session: ToolFilter::new(config.allowed_tools.as_deref(), &config.denied_tools),
+            servers: config
+                .mcp_servers
+                .iter()
+                .map(|server| {
+                    (
+                        server.display_name(),
+                        ToolFilter::new(server.allowed_tools.as_deref(), &server.denied_tools),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Also deny tools matching `patterns` for the whole session
+    pub fn deny(&mut self, patterns: &[String]) {
+        self.session.denied.extend(patterns.iter().cloned());
+    }
+
+    /// Why `tool` from `server` is blocked, naming the level that blocks it, or
+    /// `None` if it may be used
+    pub fn check(&self, server: &str, tool: &str) -> Option<String> {
+        if let Some(reason) = self.session.check(tool) {
+            return Some(format!("session {}", reason));
+        }
+        let reason = self.servers.get(server)?.check(tool)?;
+        Some(format!("server '{}' {}", server, reason))
+    }
+
+    /// Why a call of `tool` is blocked when it may come from any of `servers` (the
+    /// servers reporting a tool of that name), or `None` if it may be used
+    pub fn check_call<'a>(
+        &self,
+        mut servers: impl Iterator<Item = &'a str>,
+        tool: &str,
+    ) -> Option<String> {
+        if let Some(reason) = self.session.check(tool) {
+            return Some(format!("session {}", reason));
+        }
+        servers.find_map(|server| self.check(server, tool))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_manager::{McpConfig, McpServer};
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("read_*", "read_file"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("*_file", "write_file"));
+        assert!(glob_match("list_?", "list_a"));
+        assert!(glob_match("*a*b*", "xaybz"));
+        assert!(!glob_match("read_*", "write_file"));
+        assert!(!glob_match("list_?", "list_ab"));
+        assert!(!glob_match("read_file", "read_files"));
+    }
+
+    #[test]
+    fn test_tool_filter() {
+        let filter = ToolFilter::new(
+            Some(&["read_*".to_string(), "list_*".to_string()]),
+            &["read_secret*".to_string()],
+        );
+        assert_eq!(filter.check("read_file"), None);
+        assert_eq!(
+            filter.check("read_secrets").as_deref(),
+            Some("matches denied_tools 'read_secret*'")
+        );
+        assert_eq!(
+            filter.check("write_file").as_deref(),
+            Some("not in allowed_tools")
+        );
+        assert_eq!(ToolFilter::default().check("write_file"), None);
+    }
+
+    #[test]
+    fn test_tool_policy_levels() {
+        let mut config = ConversationConfig::default(String::new());
+        config.denied_tools = vec!["delete_*".to_string()];
+        config.mcp_servers = vec![McpServer {
+            name: Some("fs".to_string()),
+            actor_id: None,
+            config: McpConfig {
+                command: "fs-mcp-server".to_string(),
+                args: vec![],
+            },
+            tools: None,
+            allowed_tools: Some(vec!["read_*".to_string()]),
+            denied_tools: Vec::new(),
+        }];
+
+        let policy = ToolPolicy::from_config(&config);
+        assert!(policy
+            .check("other", "delete_file")
+            .unwrap()
+            .starts_with("session"));
+        assert_eq!(
+            policy.check("fs", "write_file").as_deref(),
+            Some("server 'fs' not in allowed_tools")
+        );
+        // Server lists only apply to that server's tools
+        assert_eq!(policy.check("other", "write_file"), None);
+
+        assert!(policy
+            .check_call(["fs"].into_iter(), "write_file")
+            .is_some());
+        assert!(policy
+            .check_call(std::iter::empty(), "delete_file")
+            .is_some());
+        assert_eq!(policy.check_call(std::iter::empty(), "write_file"), None);
+
+        let mut policy = policy;
+        policy.deny(&["write_*".to_string()]);
+        assert!(policy
+            .check_call(std::iter::empty(), "write_file")
+            .unwrap()
+            .starts_with("session"));
+    }
+}
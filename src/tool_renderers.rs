@@ -0,0 +1,1032 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use tracing::warn;
+
+use crate::config::ToolDisplayMode;
+use crate::tool_policy::glob_match;
+
+/// Lines of unchanged context kept around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// Largest old × new line count diffed line by line; bigger edits show as a
+/// full replacement. This keeps the table to a couple of megabytes; the chat view
+/// caches rendered cards, so a diff is only rebuilt when its card changes.
+const MAX_DIFF_CELLS: usize = 250_000;
+
+/// Input keys holding the text an edit replaces, and the replacement
+const OLD_TEXT_KEYS: &[&str] = &[
+    "old_text",
+    "old_string",
+    "old_str",
+    "old_content",
+    "oldText",
+];
+const NEW_TEXT_KEYS: &[&str] = &[
+    "new_text",
+    "new_string",
+    "new_str",
+    "new_content",
+    "newText",
+];
+
+/// Lines of a rendering shown in compact tool display mode
+const COMPACT_LINES: usize = 8;
+
+/// Widest a table column gets before its cells are cut
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Tools given a built-in renderer other than the JSON/text defaults, unless
+/// `tool_renderers` in the config says otherwise
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("edit_*", "diff"),
+    ("write_*", "diff"),
+    ("list_*", "tree"),
+    ("*directory*", "tree"),
+    ("*tree*", "tree"),
+];
+
+/// Renders a tool call's input and text output for the chat view in a given
+/// display mode. `None` means the renderer does not recognise the data, and the
+/// default (JSON for input, plain text for output) is used instead.
+pub trait ToolRenderer: std::fmt::Debug {
+    fn render_input(
+        &self,
+        _input: &Value,
+        _mode: &ToolDisplayMode,
+        _width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        None
+    }
+
+    fn render_output(
+        &self,
+        _output: &str,
+        _mode: &ToolDisplayMode,
+        _width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        None
+    }
+}
+
+/// Renderers by name, and the tools that use them
+#[derive(Debug)]
+pub struct RendererRegistry {
+    renderers: HashMap<String, Box<dyn ToolRenderer>>,
+    /// Tool name globs and the renderer used for matching tools; first match wins
+    bindings: Vec<(String, String)>,
+}
+
+impl Default for RendererRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl RendererRegistry {
+    /// The built-in `json`, `text`, `table`, `diff` and `tree` renderers with their
+    /// default bindings
+    pub fn with_builtins() -> Self {
+        let mut registry = Self {
+            renderers: HashMap::new(),
+            bindings: Vec::new(),
+        };
+        registry.register("json", Box::new(JsonRenderer));
+        registry.register("text", Box::new(TextRenderer));
+        registry.register("table", Box::new(TableRenderer));
+        registry.register("diff", Box::new(DiffRenderer));
+        registry.register("tree", Box::new(TreeRenderer));
+        for (pattern, renderer) in DEFAULT_BINDINGS {
+            registry.bind(pattern, renderer);
+        }
+        registry
+    }
+
+    /// Built-ins with the `tool_renderers` config mapping (tool name or glob to
+    /// renderer name) ahead of the default bindings. Exact names are tried before
+    /// globs, and longer globs before shorter ones.
+    pub fn from_config(mapping: &HashMap<String, String>) -> Self {
+        let mut registry = Self::with_builtins();
+        let mut configured: Vec<(&String, &String)> = mapping.iter().collect();
+        configured.sort_by_key(|(pattern, _)| {
+            (
+                pattern.contains(['*', '?']),
+                Reverse(pattern.len()),
+                pattern.to_string(),
+            )
+        });
+
+        let defaults = std::mem::take(&mut registry.bindings);
+        for (pattern, renderer) in configured {
+            if registry.renderers.contains_key(renderer) {
+                registry.bind(pattern, renderer);
+            } else {
+                warn!(
+                    "Unknown renderer '{}' for '{}' in tool_renderers",
+                    renderer, pattern
+                );
+            }
+        }
+        registry.bindings.extend(defaults);
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, renderer: Box<dyn ToolRenderer>) {
+        self.renderers.insert(name.to_string(), renderer);
+    }
+
+    /// Render tools matching the glob `pattern` with the renderer called `renderer`
+    pub fn bind(&mut self, pattern: &str, renderer: &str) {
+        self.bindings
+            .push((pattern.to_string(), renderer.to_string()));
+    }
+
+    /// Name of the renderer bound to `tool`, if any
+    pub fn renderer_name(&self, tool: &str) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, tool))
+            .map(|(_, renderer)| renderer.as_str())
+    }
+
+    fn renderer_for(&self, tool: &str) -> Option<&dyn ToolRenderer> {
+        let name = self.renderer_name(tool)?;
+        self.renderers.get(name).map(|renderer| renderer.as_ref())
+    }
+
+    /// A tool call's input, from the tool's renderer or as JSON
+    pub fn render_input(
+        &self,
+        tool: &str,
+        input: &Value,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Vec<Line<'static>> {
+        self.renderer_for(tool)
+            .and_then(|renderer| renderer.render_input(input, mode, width))
+            .or_else(|| JsonRenderer.render_input(input, mode, width))
+            .unwrap_or_default()
+    }
+
+    /// A tool's text output, from the tool's renderer or as plain text. `tool` is
+    /// `None` when the tool is unknown or the output is an error.
+    pub fn render_output(
+        &self,
+        tool: Option<&str>,
+        output: &str,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Vec<Line<'static>> {
+        tool.and_then(|tool| self.renderer_for(tool))
+            .and_then(|renderer| renderer.render_output(output, mode, width))
+            .or_else(|| TextRenderer.render_output(output, mode, width))
+            .unwrap_or_default()
+    }
+}
+
+/// Input and output as JSON: a one-line `key:value` summary in compact mode,
+/// pretty-printed in full mode
+#[derive(Debug)]
+pub struct JsonRenderer;
+
+impl ToolRenderer for JsonRenderer {
+    fn render_input(
+        &self,
+        input: &Value,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        Some(match mode {
+            ToolDisplayMode::Minimal => Vec::new(),
+            ToolDisplayMode::Compact => {
+                if input.is_null() || input.as_object().map_or(false, |obj| obj.is_empty()) {
+                    Vec::new()
+                } else {
+                    preview_line("→ ", json_preview(input, width.saturating_sub(20)))
+                }
+            }
+            ToolDisplayMode::Full if input.is_null() => indented_lines("No parameters", width),
+            ToolDisplayMode::Full => indented_lines(&pretty_json(input), width),
+        })
+    }
+
+    fn render_output(
+        &self,
+        output: &str,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        let value = serde_json::from_str::<Value>(output)
+            .ok()
+            .filter(|value| value.is_object() || value.is_array())?;
+        Some(match mode {
+            ToolDisplayMode::Minimal => Vec::new(),
+            ToolDisplayMode::Compact => {
+                preview_line("← ", json_preview(&value, width.saturating_sub(20)))
+            }
+            ToolDisplayMode::Full => indented_lines(&pretty_json(&value), width),
+        })
+    }
+}
+
+/// Input as `key: value` lines and output as plain text
+#[derive(Debug)]
+pub struct TextRenderer;
+
+impl ToolRenderer for TextRenderer {
+    fn render_input(
+        &self,
+        input: &Value,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        let fields = input.as_object()?;
+        Some(match mode {
+            ToolDisplayMode::Minimal => Vec::new(),
+            ToolDisplayMode::Compact => {
+                let preview: Vec<String> = fields
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, cell_text(value)))
+                    .collect();
+                preview_line(
+                    "→ ",
+                    fit_width(&preview.join(", "), width.saturating_sub(20)),
+                )
+            }
+            ToolDisplayMode::Full => {
+                let text: Vec<String> = fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = text_value(value);
+                        // Multi-line values start below their key
+                        if value.contains('\n') {
+                            format!("{}:\n{}", key, value)
+                        } else {
+                            format!("{}: {}", key, value)
+                        }
+                    })
+                    .collect();
+                indented_lines(&text.join("\n"), width)
+            }
+        })
+    }
+
+    fn render_output(
+        &self,
+        output: &str,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        Some(match mode {
+            ToolDisplayMode::Minimal => Vec::new(),
+            ToolDisplayMode::Compact => preview_line(
+                "← ",
+                fit_width(&output.trim().replace('\n', " "), width.saturating_sub(20)),
+            ),
+            ToolDisplayMode::Full => indented_lines(output, width),
+        })
+    }
+}
+
+/// JSON arrays of objects as a table with a column per key, and input as a
+/// field/value table
+#[derive(Debug)]
+pub struct TableRenderer;
+
+impl ToolRenderer for TableRenderer {
+    fn render_input(
+        &self,
+        input: &Value,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        let rows: Vec<Vec<String>> = input
+            .as_object()?
+            .iter()
+            .map(|(key, value)| vec![key.clone(), cell_text(value)])
+            .collect();
+        let headers = ["field".to_string(), "value".to_string()];
+        Some(fit_mode(table_lines(&headers, &rows, width), mode))
+    }
+
+    fn render_output(
+        &self,
+        output: &str,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        let (headers, rows) = parse_table(output)?;
+        Some(fit_mode(table_lines(&headers, &rows, width), mode))
+    }
+}
+
+/// File edits and writes as unified diffs
+#[derive(Debug)]
+pub struct DiffRenderer;
+
+impl ToolRenderer for DiffRenderer {
+    fn render_input(
+        &self,
+        input: &Value,
+        mode: &ToolDisplayMode,
+        width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        let lines = edit_diff(input, width).or_else(|| write_diff(input, width))?;
+        Some(fit_mode(lines, mode))
+    }
+}
+
+/// Directory listings as a tree
+#[derive(Debug)]
+pub struct TreeRenderer;
+
+impl ToolRenderer for TreeRenderer {
+    fn render_output(
+        &self,
+        output: &str,
+        mode: &ToolDisplayMode,
+        _width: usize,
+    ) -> Option<Vec<Line<'static>>> {
+        let paths = parse_listing(output)?;
+        let lines = tree_lines(&paths)
+            .into_iter()
+            .map(|line| {
+                let color = if line.ends_with('/') {
+                    Color::Blue
+                } else {
+                    Color::White
+                };
+                Line::from(Span::styled(
+                    format!("   {}", line),
+                    Style::default().fg(color),
+                ))
+            })
+            .collect();
+        Some(fit_mode(lines, mode))
+    }
+}
+
+/// Drop a rendering in minimal mode and shorten it in compact mode
+fn fit_mode(lines: Vec<Line<'static>>, mode: &ToolDisplayMode) -> Vec<Line<'static>> {
+    match mode {
+        ToolDisplayMode::Minimal => Vec::new(),
+        ToolDisplayMode::Compact => truncate_lines(lines, COMPACT_LINES),
+        ToolDisplayMode::Full => lines,
+    }
+}
+
+/// Keep the first `max` lines, noting how many were left out
+fn truncate_lines(mut lines: Vec<Line<'static>>, max: usize) -> Vec<Line<'static>> {
+    if lines.len() > max {
+        let hidden = lines.len() - max;
+        lines.truncate(max);
+        lines.push(Line::from(Span::styled(
+            format!("   … {} more lines", hidden),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+/// A compact-mode preview, `   → preview`, or nothing for an empty preview
+fn preview_line(arrow: &str, preview: String) -> Vec<Line<'static>> {
+    if preview.is_empty() {
+        return Vec::new();
+    }
+    vec![Line::from(vec![
+        Span::styled("   ".to_string(), Style::default()),
+        Span::styled(arrow.to_string(), Style::default().fg(Color::DarkGray)),
+        Span::styled(preview, Style::default().fg(Color::Gray)),
+    ])]
+}
+
+/// Full-mode text, wrapped and indented below its `Input:`/`Output:` label
+fn indented_lines(text: &str, width: usize) -> Vec<Line<'static>> {
+    textwrap::fill(text, width.saturating_sub(6))
+        .lines()
+        .map(|line| {
+            Line::from(vec![
+                Span::styled("     ".to_string(), Style::default()),
+                Span::styled(line.to_string(), Style::default().fg(Color::White)),
+            ])
+        })
+        .collect()
+}
+
+fn pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Strings as they are, anything else as JSON
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A value on one line, for previews and table cells
+fn cell_text(value: &Value) -> String {
+    text_value(value).replace('\n', " ")
+}
+
+/// Compact preview of JSON: up to three `key:value` pairs of an object, with long
+/// strings, arrays and objects abbreviated
+fn json_preview(value: &Value, max_length: usize) -> String {
+    match value {
+        Value::Object(obj) => {
+            let mut preview = String::new();
+            let mut first = true;
+
+            for (key, value) in obj.iter().take(3) {
+                if !first {
+                    preview.push_str(", ");
+                }
+                first = false;
+
+                preview.push_str(key);
+                preview.push(':');
+
+                let value_str = match value {
+                    Value::String(s) => {
+                        if s.chars().count() > 20 {
+                            format!("\"{}...\"", s.chars().take(17).collect::<String>())
+                        } else {
+                            format!("\"{}\"", s)
+                        }
+                    }
+                    Value::Array(arr) => format!("[{} items]", arr.len()),
+                    Value::Object(obj) => format!("{{{} fields}}", obj.len()),
+                    other => format!("{}", other),
+                };
+
+                preview.push_str(&value_str);
+
+                if preview.len() > max_length {
+                    break;
+                }
+            }
+
+            if obj.len() > 3 {
+                preview.push_str("...");
+            }
+
+            fit_width(&preview, max_length)
+        }
+        Value::Array(arr) => format!("[{} items]", arr.len()),
+        other => fit_width(&other.to_string(), max_length),
+    }
+}
+
+/// Column names and rows of a JSON array of objects, either top-level or under
+/// `rows`/`results`/`items`/`data`. Columns are the keys in order of appearance.
+pub fn parse_table(output: &str) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+    let value: Value = serde_json::from_str(output).ok()?;
+    let records = match &value {
+        Value::Array(records) => records,
+        Value::Object(map) => ["rows", "results", "items", "data"]
+            .iter()
+            .find_map(|key| map.get(*key)?.as_array())?,
+        _ => return None,
+    };
+
+    let mut headers: Vec<String> = Vec::new();
+    for record in records {
+        for key in record.as_object()?.keys() {
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+    }
+    if headers.is_empty() {
+        return None;
+    }
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            headers
+                .iter()
+                .map(|header| record.get(header).map(cell_text).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    Some((headers, rows))
+}
+
+/// Left-aligned columns two spaces apart under a bold header, cut to `width`
+fn table_lines(headers: &[String], rows: &[Vec<String>], width: usize) -> Vec<Line<'static>> {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect();
+    for row in rows {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(cell.chars().count());
+        }
+    }
+    for column_width in &mut widths {
+        *column_width = (*column_width).min(MAX_COLUMN_WIDTH);
+    }
+
+    let text_width = width.saturating_sub(3);
+    let format_row = |cells: &[String]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", fit_width(cell, *width), width = *width))
+            .collect();
+        format!("   {}", fit_width(cells.join("  ").trim_end(), text_width))
+    };
+
+    let rule_width =
+        (widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1)).min(text_width);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format_row(headers),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            format!("   {}", "─".repeat(rule_width)),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    lines.extend(rows.iter().map(|row| {
+        Line::from(Span::styled(
+            format_row(row),
+            Style::default().fg(Color::White),
+        ))
+    }));
+    lines
+}
+
+/// One line of a line-by-line diff
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Diff two texts line by line (longest common subsequence)
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    if old.len() * new.len() > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string()))
+            .chain(new.iter().map(|line| DiffLine::Added(line.to_string())))
+            .collect();
+    }
+
+    // lcs[i][j] is the common subsequence length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Context(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(
+        old[i..]
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string())),
+    );
+    diff.extend(
+        new[j..]
+            .iter()
+            .map(|line| DiffLine::Added(line.to_string())),
+    );
+    diff
+}
+
+/// A run of changes with surrounding context, as in a unified diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffHunk {
+    /// First old line in the hunk (1-based)
+    pub old_start: usize,
+    pub old_len: usize,
+    /// First new line in the hunk (1-based)
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Group a diff into hunks keeping `context` unchanged lines around each change
+pub fn diff_hunks(lines: &[DiffLine], context: usize) -> Vec<DiffHunk> {
+    // Old and new line numbers at the start of each diff line
+    let mut positions = Vec::with_capacity(lines.len());
+    let (mut old_line, mut new_line) = (1, 1);
+    for line in lines {
+        positions.push((old_line, new_line));
+        match line {
+            DiffLine::Context(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffLine::Removed(_) => old_line += 1,
+            DiffLine::Added(_) => new_line += 1,
+        }
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if matches!(line, DiffLine::Context(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let lines = lines[start..end].to_vec();
+            DiffHunk {
+                old_start: positions[start].0,
+                old_len: lines
+                    .iter()
+                    .filter(|line| !matches!(line, DiffLine::Added(_)))
+                    .count(),
+                new_start: positions[start].1,
+                new_len: lines
+                    .iter()
+                    .filter(|line| !matches!(line, DiffLine::Removed(_)))
+                    .count(),
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// Unified-diff view of an edit: a header with the path and change counts, then
+/// each hunk with red removed and green added lines
+pub fn render_diff(path: Option<&str>, old: &str, new: &str, width: usize) -> Vec<Line<'static>> {
+    let diff = diff_lines(old, new);
+    let added = diff
+        .iter()
+        .filter(|line| matches!(line, DiffLine::Added(_)))
+        .count();
+    let removed = diff
+        .iter()
+        .filter(|line| matches!(line, DiffLine::Removed(_)))
+        .count();
+
+    let mut lines = vec![Line::from(vec![
+        Span::raw("   "),
+        Span::styled(
+            path.unwrap_or("(no path)").to_string(),
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(format!(" +{}", added), Style::default().fg(Color::Green)),
+        Span::styled(format!(" -{}", removed), Style::default().fg(Color::Red)),
+    ])];
+
+    let text_width = width.saturating_sub(5);
+    for hunk in diff_hunks(&diff, DIFF_CONTEXT) {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "   @@ -{},{} +{},{} @@",
+                hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len
+            ),
+            Style::default().fg(Color::Cyan),
+        )));
+        for line in hunk.lines {
+            let (marker, text, color) = match line {
+                DiffLine::Context(text) => (' ', text, Color::Gray),
+                DiffLine::Removed(text) => ('-', text, Color::Red),
+                DiffLine::Added(text) => ('+', text, Color::Green),
+            };
+            lines.push(Line::from(Span::styled(
+                format!("   {} {}", marker, fit_width(&text, text_width)),
+                Style::default().fg(color),
+            )));
+        }
+    }
+    lines
+}
+
+/// Cut a line to `width` characters, marking the cut
+fn fit_width(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+fn string_field<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| value.get(*key)?.as_str())
+}
+
+fn path_field(input: &Value) -> Option<&str> {
+    string_field(input, &["path", "file_path", "filePath", "file"])
+}
+
+/// Edits as `old_text`/`new_text` pairs, either top-level or in an `edits` array
+fn edit_diff(input: &Value, width: usize) -> Option<Vec<Line<'static>>> {
+    let path = path_field(input);
+    let pair = |edit: &Value| {
+        Some((
+            string_field(edit, OLD_TEXT_KEYS)?.to_string(),
+            string_field(edit, NEW_TEXT_KEYS)?.to_string(),
+        ))
+    };
+
+    let edits: Vec<(String, String)> = match input.get("edits").and_then(Value::as_array) {
+        Some(edits) => edits.iter().filter_map(pair).collect(),
+        None => pair(input).into_iter().collect(),
+    };
+    if edits.is_empty() {
+        return None;
+    }
+
+    Some(
+        edits
+            .iter()
+            .flat_map(|(old, new)| render_diff(path, old, new, width))
+            .collect(),
+    )
+}
+
+/// A whole-file write, shown as a diff from an empty file
+fn write_diff(input: &Value, width: usize) -> Option<Vec<Line<'static>>> {
+    let content = string_field(input, &["content", "text", "contents"])?;
+    Some(render_diff(path_field(input), "", content, width))
+}
+
+/// Paths from a directory listing, directories ending in `/`. Understands JSON
+/// arrays of paths or `{name|path, type|is_dir}` objects, and plain text with one
+/// entry per line, optionally prefixed `[DIR]`/`[FILE]`.
+pub fn parse_listing(output: &str) -> Option<Vec<String>> {
+    if let Ok(value) = serde_json::from_str::<Value>(output) {
+        let entries = match &value {
+            Value::Array(entries) => entries,
+            Value::Object(map) => ["entries", "files", "items"]
+                .iter()
+                .find_map(|key| map.get(*key)?.as_array())?,
+            _ => return None,
+        };
+        let paths: Vec<String> = entries.iter().filter_map(listing_entry).collect();
+        return (!paths.is_empty()).then_some(paths);
+    }
+
+    let paths: Vec<String> = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if let Some(dir) = line.strip_prefix("[DIR]") {
+                format!("{}/", dir.trim().trim_end_matches('/'))
+            } else if let Some(file) = line.strip_prefix("[FILE]") {
+                file.trim().to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    (!paths.is_empty()).then_some(paths)
+}
+
+fn listing_entry(entry: &Value) -> Option<String> {
+    if let Some(path) = entry.as_str() {
+        return Some(path.to_string());
+    }
+    let path = string_field(entry, &["path", "name"])?;
+    let is_dir = entry
+        .get("is_dir")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+        || matches!(
+            string_field(entry, &["type", "kind"]),
+            Some("dir" | "directory")
+        );
+    Some(if is_dir && !path.ends_with('/') {
+        format!("{}/", path)
+    } else {
+        path.to_string()
+    })
+}
+
+#[derive(Default)]
+struct TreeNode {
+    is_dir: bool,
+    children: BTreeMap<String, TreeNode>,
+}
+
+/// Draw paths as a tree with `├──`/`└──` branches, directories first
+pub fn tree_lines(paths: &[String]) -> Vec<String> {
+    let mut root = TreeNode::default();
+    for path in paths {
+        let is_dir = path.ends_with('/');
+        let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+        let mut node = &mut root;
+        for (index, part) in parts.iter().enumerate() {
+            node = node.children.entry(part.to_string()).or_default();
+            if index + 1 < parts.len() || is_dir {
+                node.is_dir = true;
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    push_tree(&root, "", &mut lines);
+    lines
+}
+
+fn push_tree(node: &TreeNode, prefix: &str, lines: &mut Vec<String>) {
+    let mut children: Vec<(&String, &TreeNode)> = node.children.iter().collect();
+    children.sort_by_key(|(name, child)| (!child.is_dir, name.to_string()));
+
+    for (index, (name, child)) in children.iter().enumerate() {
+        let last = index + 1 == children.len();
+        let branch = if last { "└── " } else { "├── " };
+        let suffix = if child.is_dir { "/" } else { "" };
+        lines.push(format!("{}{}{}{}", prefix, branch, name, suffix));
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        push_tree(child, &child_prefix, lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc\n", "a\nB\nc\nd\n");
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Context("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Added("B".to_string()),
+                DiffLine::Context("c".to_string()),
+                DiffLine::Added("d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let old: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "");
+        let hunks = diff_hunks(&diff_lines(&old, &new), 3);
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].old_len), (1, 5));
+        assert_eq!((hunks[0].new_start, hunks[0].new_len), (1, 5));
+        assert_eq!((hunks[1].old_start, hunks[1].old_len), (15, 6));
+        assert_eq!((hunks[1].new_start, hunks[1].new_len), (15, 5));
+    }
+
+    #[test]
+    fn test_tree_lines() {
+        let paths = vec![
+            "src/main.rs".to_string(),
+            "Cargo.toml".to_string(),
+            "src/ui/".to_string(),
+            "README.md".to_string(),
+        ];
+        assert_eq!(
+            tree_lines(&paths),
+            vec![
+                "├── src/",
+                "│   ├── ui/",
+                "│   └── main.rs",
+                "├── Cargo.toml",
+                "└── README.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_listing() {
+        assert_eq!(
+            parse_listing("[DIR] src\n[FILE] Cargo.toml\n"),
+            Some(vec!["src/".to_string(), "Cargo.toml".to_string()])
+        );
+        assert_eq!(
+            parse_listing(r#"[{"name": "src", "type": "directory"}, {"name": "a.rs"}]"#),
+            Some(vec!["src/".to_string(), "a.rs".to_string()])
+        );
+        assert_eq!(parse_listing("  \n"), None);
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let (headers, rows) =
+            parse_table(r#"[{"name": "a", "size": 1}, {"name": "b", "mode": "rw"}]"#).unwrap();
+        assert_eq!(headers, vec!["name", "size", "mode"]);
+        assert_eq!(rows[1], vec!["b", "", "rw"]);
+
+        let lines = table_lines(&headers, &rows, 80);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].spans[0].content, "   name  size  mode");
+        assert_eq!(lines[3].spans[0].content, "   b           rw");
+
+        assert!(parse_table(r#"{"rows": [{"id": 1}]}"#).is_some());
+        assert!(parse_table("[1, 2]").is_none());
+        assert!(parse_table("[]").is_none());
+    }
+
+    #[test]
+    fn test_registry_defaults() {
+        let registry = RendererRegistry::with_builtins();
+        let edit = json!({ "path": "a.rs", "old_text": "x\n", "new_text": "y\n" });
+        let full = ToolDisplayMode::Full;
+
+        assert_eq!(
+            registry.render_input("edit_file", &edit, &full, 80).len(),
+            4
+        );
+        assert!(registry
+            .render_input("edit_file", &edit, &ToolDisplayMode::Minimal, 80)
+            .is_empty());
+        // Input the diff renderer does not recognise falls back to JSON
+        assert_eq!(
+            registry
+                .render_input("edit_file", &json!({ "path": "a.rs" }), &full, 80)
+                .len(),
+            3
+        );
+        assert_eq!(registry.renderer_name("read_file"), None);
+        assert_eq!(
+            registry
+                .render_output(Some("list_directory"), "[DIR] src\n[FILE] a.rs", &full, 80)
+                .len(),
+            2
+        );
+        // Errors are rendered as text whatever the tool
+        assert_eq!(
+            registry.render_output(None, "[DIR] src", &full, 80)[0].spans[1].content,
+            "[DIR] src"
+        );
+    }
+
+    #[test]
+    fn test_registry_from_config() {
+        let mapping: HashMap<String, String> = [
+            ("list_*", "table"),
+            ("list_directory", "text"),
+            ("query_*", "table"),
+            ("*", "nonexistent"),
+        ]
+        .iter()
+        .map(|(pattern, renderer)| (pattern.to_string(), renderer.to_string()))
+        .collect();
+        let registry = RendererRegistry::from_config(&mapping);
+
+        // Exact names beat globs, and the config beats the default bindings
+        assert_eq!(registry.renderer_name("list_directory"), Some("text"));
+        assert_eq!(registry.renderer_name("list_files"), Some("table"));
+        assert_eq!(registry.renderer_name("query_db"), Some("table"));
+        assert_eq!(registry.renderer_name("edit_file"), Some("diff"));
+        // Bindings to unknown renderers are dropped
+        assert_eq!(registry.renderer_name("read_file"), None);
+
+        // The config schema accepts exactly the built-in renderers
+        let mut builtins: Vec<&str> = registry.renderers.keys().map(String::as_str).collect();
+        builtins.sort_unstable();
+        let mut names = crate::config_schema::RENDERER_NAMES.to_vec();
+        names.sort_unstable();
+        assert_eq!(builtins, names);
+    }
+}
@@ -9,69 +9,15 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{App, AppMode};
-use crate::config::{CompatibleArgs, ToolDisplayMode};
+use crate::app::{App, AppMode, PendingApproval};
+use crate::chat::ErrorInfo;
+use crate::config::{CompatibleArgs, ToolDisplayMode, CONTEXT_WARNING_THRESHOLD};
+use crate::tool_calls::{count_tool_calls, format_duration, format_size, ToolCall, ToolCallStatus};
+use crate::tool_inventory::{schema_summary, ServerState};
+use crate::tool_renderers::RendererRegistry;
 use genai_types::Message;
-
-/// Create a compact preview of tool input parameters
-fn create_compact_input_preview(input: &serde_json::Value, max_length: usize) -> String {
-    match input {
-        serde_json::Value::Object(obj) => {
-            let mut preview = String::new();
-            let mut first = true;
-
-            for (key, value) in obj.iter().take(3) {
-                // Show max 3 params
-                if !first {
-                    preview.push_str(", ");
-                }
-                first = false;
-
-                // Show key and abbreviated value
-                preview.push_str(key);
-                preview.push(':');
-
-                let value_str = match value {
-                    serde_json::Value::String(s) => {
-                        if s.len() > 20 {
-                            format!("\"{}...\"", &s[..17])
-                        } else {
-                            format!("\"{}\"", s)
-                        }
-                    }
-                    serde_json::Value::Array(arr) => format!("[{} items]", arr.len()),
-                    serde_json::Value::Object(obj) => format!("{{{} fields}}", obj.len()),
-                    other => format!("{}", other),
-                };
-
-                preview.push_str(&value_str);
-
-                if preview.len() > max_length {
-                    break;
-                }
-            }
-
-            if obj.len() > 3 {
-                preview.push_str("...");
-            }
-
-            if preview.len() > max_length {
-                preview.truncate(max_length.saturating_sub(3));
-                preview.push_str("...");
-            }
-
-            preview
-        }
-        other => {
-            let s = format!("{}", other);
-            if s.len() > max_length {
-                format!("{}...", &s[..max_length.saturating_sub(3)])
-            } else {
-                s
-            }
-        }
-    }
-}
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 /// Create a compact preview of tool output
 fn create_compact_output_preview(
@@ -244,6 +190,19 @@ pub fn render_loading_screen(f: &mut Frame, app: &App, _args: &CompatibleArgs) {
 
         lines.push(line);
 
+        // Add warning details if step completed with a warning
+        if let crate::config::StepStatus::Warning(warning) = &step.status {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    "  Warning: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(warning, Style::default().fg(Color::Yellow)),
+            ]));
+        }
+
         // Add error details if step failed
         if let crate::config::StepStatus::Failed(error) = &step.status {
             lines.push(Line::from(vec![
@@ -336,6 +295,19 @@ pub fn render_chat_screen(f: &mut Frame, app: &mut App, args: &CompatibleArgs) {
     if app.show_help {
         render_help_popup(f, size);
     }
+
+    if app.show_tools {
+        render_tools_popup(f, size, app);
+    }
+
+    if app.show_timeline {
+        render_timeline_popup(f, size, app);
+    }
+
+    // Drawn last so it stays on top: nothing else takes keys while a tool call waits
+    if let Some(approval) = app.pending_approvals.front() {
+        render_approval_popup(f, size, approval, app.pending_approvals.len() - 1);
+    }
 }
 
 /// Render the split screen layout
@@ -358,14 +330,18 @@ fn render_split_screen_layout(f: &mut Frame, size: ratatui::layout::Rect, app: &
     // Calculate input area height dynamically
     let input_height = app.get_input_height();
 
+    // Show the context warning banner only when approaching the limit
+    let banner_height = if context_warning(app, args).is_some() { 1 } else { 0 };
+
     // Create main layout for left side (chat interface)
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1),            // Title bar
-            Constraint::Min(1),               // Chat area (takes remaining space)
-            Constraint::Length(input_height), // Input area (flexible)
-            Constraint::Length(1),            // Status bar
+            Constraint::Length(1),             // Title bar
+            Constraint::Min(1),                // Chat area (takes remaining space)
+            Constraint::Length(banner_height), // Context warning banner
+            Constraint::Length(input_height),  // Input area (flexible)
+            Constraint::Length(1),             // Status bar
         ])
         .split(horizontal_chunks[0]);
 
@@ -375,11 +351,14 @@ fn render_split_screen_layout(f: &mut Frame, size: ratatui::layout::Rect, app: &
     // Chat messages area (left side)
     render_chat_area(f, left_chunks[1], app);
 
+    // Context warning banner (left side)
+    render_context_warning(f, left_chunks[2], app, args);
+
     // Input area (left side)
-    render_flexible_input_area(f, left_chunks[2], app);
+    render_flexible_input_area(f, left_chunks[3], app);
 
     // Status bar (left side)
-    render_status_bar(f, left_chunks[3], app, args);
+    render_status_bar(f, left_chunks[4], app, args);
 
     // Right side - help panel
     render_help_panel(f, horizontal_chunks[1], app);
@@ -396,14 +375,18 @@ fn render_full_screen_layout(f: &mut Frame, size: ratatui::layout::Rect, app: &m
     // Calculate input area height dynamically
     let input_height = app.get_input_height();
 
+    // Show the context warning banner only when approaching the limit
+    let banner_height = if context_warning(app, args).is_some() { 1 } else { 0 };
+
     // Create main layout with flexible input area
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1),            // Title bar
-            Constraint::Min(1),               // Chat area (takes remaining space)
-            Constraint::Length(input_height), // Input area (flexible)
-            Constraint::Length(1),            // Status bar
+            Constraint::Length(1),             // Title bar
+            Constraint::Min(1),                // Chat area (takes remaining space)
+            Constraint::Length(banner_height), // Context warning banner
+            Constraint::Length(input_height),  // Input area (flexible)
+            Constraint::Length(1),             // Status bar
         ])
         .split(size);
 
@@ -413,11 +396,14 @@ fn render_full_screen_layout(f: &mut Frame, size: ratatui::layout::Rect, app: &m
     // Chat messages area
     render_chat_area(f, chunks[1], app);
 
+    // Context warning banner
+    render_context_warning(f, chunks[2], app, args);
+
     // Input area
-    render_flexible_input_area(f, chunks[2], app);
+    render_flexible_input_area(f, chunks[3], app);
 
     // Status bar
-    render_status_bar(f, chunks[3], app, args);
+    render_status_bar(f, chunks[4], app, args);
 }
 
 /// Render the title bar
@@ -438,6 +424,8 @@ fn format_message_content(
     content: &MessageContent,
     available_width: usize,
     tool_display_mode: &ToolDisplayMode,
+    tool_names: &HashMap<String, String>,
+    renderers: &RendererRegistry,
 ) -> Vec<Line<'static>> {
     match content {
         MessageContent::Text { text } => {
@@ -448,43 +436,17 @@ fn format_message_content(
                 .collect()
         }
         MessageContent::ToolUse { id, name, input } => {
+            let mut lines = Vec::new();
             match tool_display_mode {
-                ToolDisplayMode::Minimal => {
-                    vec![Line::from(vec![Span::styled(
-                        name.clone(),
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD),
-                    )])]
-                }
-                ToolDisplayMode::Compact => {
-                    let mut lines = vec![Line::from(vec![Span::styled(
+                ToolDisplayMode::Minimal | ToolDisplayMode::Compact => {
+                    lines.push(Line::from(vec![Span::styled(
                         name.clone(),
                         Style::default()
                             .fg(Color::Cyan)
                             .add_modifier(Modifier::BOLD),
-                    )])];
-
-                    if !input.is_null() && !input.as_object().map_or(false, |obj| obj.is_empty()) {
-                        let input_preview =
-                            create_compact_input_preview(input, available_width.saturating_sub(20));
-                        if !input_preview.is_empty() {
-                            lines.push(Line::from(vec![
-                                Span::styled("   ".to_string(), Style::default()),
-                                Span::styled(
-                                    "→ ".to_string(),
-                                    Style::default().fg(Color::DarkGray),
-                                ),
-                                Span::styled(input_preview, Style::default().fg(Color::Gray)),
-                            ]));
-                        }
-                    }
-                    lines
+                    )]));
                 }
                 ToolDisplayMode::Full => {
-                    // Original full display
-                    let mut lines = Vec::new();
-
                     lines.push(Line::from(vec![
                         Span::styled(
                             "Tool Use: ".to_string(),
@@ -505,32 +467,14 @@ fn format_message_content(
                         Span::styled(id.clone(), Style::default().fg(Color::DarkGray)),
                     ]));
 
-                    let input_str = if input.is_null() {
-                        "No parameters".to_string()
-                    } else {
-                        match serde_json::to_string_pretty(input) {
-                            Ok(formatted) => formatted,
-                            Err(_) => format!("{}", input),
-                        }
-                    };
-
                     lines.push(Line::from(vec![Span::styled(
                         "   Input: ".to_string(),
                         Style::default().fg(Color::Yellow),
                     )]));
-
-                    let wrapped_input =
-                        textwrap::fill(&input_str, available_width.saturating_sub(6));
-                    for line in wrapped_input.lines() {
-                        lines.push(Line::from(vec![
-                            Span::styled("     ".to_string(), Style::default()),
-                            Span::styled(line.to_string(), Style::default().fg(Color::White)),
-                        ]));
-                    }
-
-                    lines
                 }
             }
+            lines.extend(renderers.render_input(name, input, tool_display_mode, available_width));
+            lines
         }
         MessageContent::ToolResult {
             tool_use_id,
@@ -538,6 +482,11 @@ fn format_message_content(
             is_error,
         } => {
             let is_error = is_error.unwrap_or(false);
+            // Error output is shown as text whatever the tool
+            let tool = tool_names
+                .get(tool_use_id)
+                .filter(|_| !is_error)
+                .map(String::as_str);
             match tool_display_mode {
                 ToolDisplayMode::Minimal => {
                     let (symbol, color) = if is_error {
@@ -562,16 +511,13 @@ fn format_message_content(
                         symbol.to_string(),
                         Style::default().fg(color).add_modifier(Modifier::BOLD),
                     )])];
-
-                    let output_preview =
-                        create_compact_output_preview(content, available_width.saturating_sub(20));
-                    if !output_preview.is_empty() {
-                        lines.push(Line::from(vec![
-                            Span::styled("   ".to_string(), Style::default()),
-                            Span::styled("← ".to_string(), Style::default().fg(Color::DarkGray)),
-                            Span::styled(output_preview, Style::default().fg(Color::Gray)),
-                        ]));
-                    }
+                    lines.extend(format_tool_output(
+                        content,
+                        tool,
+                        available_width,
+                        tool_display_mode,
+                        renderers,
+                    ));
                     lines
                 }
                 ToolDisplayMode::Full => {
@@ -605,65 +551,13 @@ fn format_message_content(
                         Span::styled(tool_use_id.clone(), Style::default().fg(Color::DarkGray)),
                     ]));
 
-                    for tool_content in content {
-                        match tool_content {
-                            mcp_protocol::tool::ToolContent::Text { text } => {
-                                lines.push(Line::from(vec![Span::styled(
-                                    "   Output: ".to_string(),
-                                    Style::default().fg(Color::Cyan),
-                                )]));
-
-                                let wrapped_output =
-                                    textwrap::fill(text, available_width.saturating_sub(6));
-                                for line in wrapped_output.lines() {
-                                    lines.push(Line::from(vec![
-                                        Span::styled("     ".to_string(), Style::default()),
-                                        Span::styled(
-                                            line.to_string(),
-                                            Style::default().fg(Color::White),
-                                        ),
-                                    ]));
-                                }
-                            }
-                            mcp_protocol::tool::ToolContent::Image { data, mime_type } => {
-                                lines.push(Line::from(vec![
-                                    Span::styled(
-                                        "   Image: ".to_string(),
-                                        Style::default().fg(Color::Cyan),
-                                    ),
-                                    Span::styled(
-                                        format!("{} ({} bytes)", mime_type, data.len()),
-                                        Style::default().fg(Color::White),
-                                    ),
-                                ]));
-                            }
-                            mcp_protocol::tool::ToolContent::Audio { data, mime_type } => {
-                                lines.push(Line::from(vec![
-                                    Span::styled(
-                                        "   Audio: ".to_string(),
-                                        Style::default().fg(Color::Cyan),
-                                    ),
-                                    Span::styled(
-                                        format!("{} ({} bytes)", mime_type, data.len()),
-                                        Style::default().fg(Color::White),
-                                    ),
-                                ]));
-                            }
-                            mcp_protocol::tool::ToolContent::Resource { resource } => {
-                                lines.push(Line::from(vec![
-                                    Span::styled(
-                                        "   Resource: ".to_string(),
-                                        Style::default().fg(Color::Cyan),
-                                    ),
-                                    Span::styled(
-                                        format!("{}", resource),
-                                        Style::default().fg(Color::White),
-                                    ),
-                                ]));
-                            }
-                        }
-                    }
-
+                    lines.extend(format_tool_output(
+                        content,
+                        tool,
+                        available_width,
+                        tool_display_mode,
+                        renderers,
+                    ));
                     lines
                 }
             }
@@ -671,6 +565,317 @@ fn format_message_content(
     }
 }
 
+/// Format the content of a tool result for the compact or full display mode
+fn format_tool_output(
+    content: &[mcp_protocol::tool::ToolContent],
+    tool: Option<&str>,
+    available_width: usize,
+    tool_display_mode: &ToolDisplayMode,
+    renderers: &RendererRegistry,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    match tool_display_mode {
+        ToolDisplayMode::Minimal => {}
+        ToolDisplayMode::Compact => {
+            let texts: Vec<&String> = content
+                .iter()
+                .filter_map(|tool_content| match tool_content {
+                    mcp_protocol::tool::ToolContent::Text { text } => Some(text),
+                    _ => None,
+                })
+                .collect();
+            for text in &texts {
+                lines.extend(renderers.render_output(
+                    tool,
+                    text,
+                    tool_display_mode,
+                    available_width,
+                ));
+            }
+
+            // Images, audio and resources only get a one-line summary
+            let output_preview =
+                create_compact_output_preview(content, available_width.saturating_sub(20));
+            if texts.is_empty() && !output_preview.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("   ".to_string(), Style::default()),
+                    Span::styled("← ".to_string(), Style::default().fg(Color::DarkGray)),
+                    Span::styled(output_preview, Style::default().fg(Color::Gray)),
+                ]));
+            }
+        }
+        ToolDisplayMode::Full => {
+            for tool_content in content {
+                match tool_content {
+                    mcp_protocol::tool::ToolContent::Text { text } => {
+                        lines.push(Line::from(vec![Span::styled(
+                            "   Output: ".to_string(),
+                            Style::default().fg(Color::Cyan),
+                        )]));
+                        lines.extend(renderers.render_output(
+                            tool,
+                            text,
+                            tool_display_mode,
+                            available_width,
+                        ));
+                    }
+                    mcp_protocol::tool::ToolContent::Image { data, mime_type } => {
+                        lines.push(Line::from(vec![
+                            Span::styled(
+                                "   Image: ".to_string(),
+                                Style::default().fg(Color::Cyan),
+                            ),
+                            Span::styled(
+                                format!("{} ({} bytes)", mime_type, data.len()),
+                                Style::default().fg(Color::White),
+                            ),
+                        ]));
+                    }
+                    mcp_protocol::tool::ToolContent::Audio { data, mime_type } => {
+                        lines.push(Line::from(vec![
+                            Span::styled(
+                                "   Audio: ".to_string(),
+                                Style::default().fg(Color::Cyan),
+                            ),
+                            Span::styled(
+                                format!("{} ({} bytes)", mime_type, data.len()),
+                                Style::default().fg(Color::White),
+                            ),
+                        ]));
+                    }
+                    mcp_protocol::tool::ToolContent::Resource { resource } => {
+                        lines.push(Line::from(vec![
+                            Span::styled(
+                                "   Resource: ".to_string(),
+                                Style::default().fg(Color::Cyan),
+                            ),
+                            Span::styled(
+                                format!("{}", resource),
+                                Style::default().fg(Color::White),
+                            ),
+                        ]));
+                    }
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Rendered bodies of tool call cards by tool use ID. A body can hold a line diff,
+/// so it is kept between redraws and rebuilt only when the width, the display mode
+/// or the presence of a result changes.
+#[derive(Debug, Default)]
+pub struct ToolCardCache {
+    cards: HashMap<String, CachedCardBody>,
+}
+
+#[derive(Debug)]
+struct CachedCardBody {
+    width: usize,
+    mode: ToolDisplayMode,
+    has_result: bool,
+    lines: Vec<Line<'static>>,
+}
+
+impl ToolCardCache {
+    /// Drop the bodies of calls no longer in the conversation
+    pub fn retain(&mut self, calls: &[ToolCall]) {
+        let ids: HashSet<&str> = calls.iter().map(|call| call.id.as_str()).collect();
+        self.cards.retain(|id, _| ids.contains(id.as_str()));
+    }
+
+    fn body(
+        &mut self,
+        call: &ToolCall,
+        available_width: usize,
+        tool_display_mode: &ToolDisplayMode,
+        renderers: &RendererRegistry,
+    ) -> &[Line<'static>] {
+        let has_result = call.result.is_some();
+        let fresh = self.cards.get(&call.id).map_or(false, |card| {
+            card.width == available_width
+                && card.mode == *tool_display_mode
+                && card.has_result == has_result
+        });
+        if !fresh {
+            let lines = format_tool_card_body(call, available_width, tool_display_mode, renderers);
+            self.cards.insert(
+                call.id.clone(),
+                CachedCardBody {
+                    width: available_width,
+                    mode: tool_display_mode.clone(),
+                    has_result,
+                    lines,
+                },
+            );
+        }
+        &self.cards[&call.id].lines
+    }
+}
+
+/// Format a tool call and its result as one card: a header with the tool name,
+/// status and duration, then the input and output unless the card is collapsed
+fn format_tool_card(
+    call: &ToolCall,
+    duration: Option<Duration>,
+    collapsed: bool,
+    available_width: usize,
+    tool_display_mode: &ToolDisplayMode,
+    renderers: &RendererRegistry,
+    cache: &mut ToolCardCache,
+) -> Vec<Line<'static>> {
+    let status = call.status();
+    let (symbol, color) = match status {
+        ToolCallStatus::Pending => ("⋯ running", Color::Yellow),
+        ToolCallStatus::Succeeded => ("✓", Color::Green),
+        ToolCallStatus::Failed => ("✗ error", Color::Red),
+        ToolCallStatus::Blocked => ("⊘ blocked", Color::LightRed),
+    };
+    let has_body = *tool_display_mode != ToolDisplayMode::Minimal;
+
+    let mut header = Vec::new();
+    if has_body {
+        header.push(Span::styled(
+            if collapsed { "▸ " } else { "▾ " }.to_string(),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    header.push(Span::styled(
+        call.name.clone(),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+    header.push(Span::styled(
+        format!(" {}", symbol),
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    ));
+    if let Some(duration) = duration {
+        header.push(Span::styled(
+            format!(" · {}", format_duration(duration)),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let mut lines = vec![Line::from(header)];
+    if !collapsed && has_body {
+        lines.extend(
+            cache
+                .body(call, available_width, tool_display_mode, renderers)
+                .iter()
+                .cloned(),
+        );
+    }
+    lines
+}
+
+/// The input and output shown below a tool card's header
+fn format_tool_card_body(
+    call: &ToolCall,
+    available_width: usize,
+    tool_display_mode: &ToolDisplayMode,
+    renderers: &RendererRegistry,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if *tool_display_mode == ToolDisplayMode::Full {
+        lines.push(Line::from(vec![
+            Span::styled("   ID: ".to_string(), Style::default().fg(Color::DarkGray)),
+            Span::styled(call.id.clone(), Style::default().fg(Color::DarkGray)),
+        ]));
+        lines.push(Line::from(vec![Span::styled(
+            "   Input: ".to_string(),
+            Style::default().fg(Color::Yellow),
+        )]));
+    }
+    lines.extend(renderers.render_input(
+        &call.name,
+        &call.input,
+        tool_display_mode,
+        available_width,
+    ));
+
+    match &call.result {
+        Some(result) => {
+            // Error output is shown as text whatever the tool
+            let tool = (!result.is_error).then_some(call.name.as_str());
+            lines.extend(format_tool_output(
+                &result.content,
+                tool,
+                available_width,
+                tool_display_mode,
+                renderers,
+            ));
+        }
+        None => lines.push(Line::from(Span::styled(
+            "   ⋯ awaiting result".to_string(),
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        ))),
+    }
+    lines
+}
+
+/// Format an error entry with its code, message, details and retry hint
+fn format_error_entry(
+    error: &ErrorInfo,
+    available_width: usize,
+    is_selected: bool,
+) -> Vec<Line<'static>> {
+    let background = if is_selected { Color::DarkGray } else { Color::Reset };
+    let border = Span::styled("┃ ", Style::default().fg(Color::Red).bg(background));
+
+    let mut lines = vec![Line::from(vec![
+        border.clone(),
+        Span::styled(
+            format!("Error [{}]", error.code),
+            Style::default()
+                .fg(Color::Red)
+                .bg(background)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])];
+
+    for line in textwrap::fill(&error.message, available_width.saturating_sub(2)).lines() {
+        lines.push(Line::from(vec![
+            border.clone(),
+            Span::styled(line.to_string(), Style::default().fg(Color::LightRed).bg(background)),
+        ]));
+    }
+
+    if let Some(details) = &error.details {
+        let mut details: Vec<_> = details.iter().collect();
+        details.sort();
+        for (key, value) in details {
+            let detail = format!("{}: {}", key, value);
+            for line in textwrap::fill(&detail, available_width.saturating_sub(4)).lines() {
+                lines.push(Line::from(vec![
+                    border.clone(),
+                    Span::styled(
+                        format!("  {}", line),
+                        Style::default().fg(Color::Gray).bg(background),
+                    ),
+                ]));
+            }
+        }
+    }
+
+    lines.push(Line::from(vec![
+        border,
+        Span::styled(
+            if is_selected {
+                "Press r to retry"
+            } else {
+                "Select with v, then press r to retry"
+            },
+            Style::default().fg(Color::DarkGray).bg(background),
+        ),
+    ]));
+
+    lines
+}
+
 /// Render the chat messages area with enhanced tool use support and message navigation
 fn render_chat_area(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
     let messages_block = Block::default().borders(Borders::ALL);
@@ -682,6 +887,20 @@ fn render_chat_area(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
     let mut all_items = Vec::new();
     let selected_message_index = app.get_selected_message_index();
 
+    // Tool uses paired with their results (results only carry the ID of their call),
+    // kept by the app between redraws
+    let tool_names: HashMap<String, String> = app
+        .paired_tool_calls
+        .iter()
+        .map(|call| (call.id.clone(), call.name.clone()))
+        .collect();
+    // Calls shown as a card with their result
+    let cards: HashMap<&str, &ToolCall> = app
+        .paired_tool_calls
+        .iter()
+        .map(|call| (call.id.as_str(), call))
+        .collect();
+
     for (msg_index, chat_msg) in app.messages.iter().enumerate() {
         let message = chat_msg.as_message();
         let mut role_style = match message.role {
@@ -719,6 +938,15 @@ fn render_chat_area(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
             ))));
         }
 
+        // Errors from the actor get their own distinct rendering
+        if let Some(error) = chat_msg.as_error() {
+            for line in format_error_entry(error, available_width, is_selected) {
+                all_items.push(ListItem::new(line));
+            }
+            all_items.push(ListItem::new(Line::from("")));
+            continue;
+        }
+
         // Process each content item in the message
         let is_collapsed = app.is_message_collapsed(msg_index);
 
@@ -772,9 +1000,35 @@ fn render_chat_area(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
                 Role::System => Color::Yellow,
             };
 
+            let mut rendered_lines = 0;
             for content in &message.content {
-                let content_lines =
-                    format_message_content(content, available_width, &app.tool_display_mode);
+                let content_lines = match content {
+                    // Results are shown in the card of their tool call
+                    MessageContent::ToolResult { tool_use_id, .. }
+                        if cards.contains_key(tool_use_id.as_str()) =>
+                    {
+                        continue;
+                    }
+                    MessageContent::ToolUse { id, .. } if cards.contains_key(id.as_str()) => {
+                        format_tool_card(
+                            cards[id.as_str()],
+                            app.tool_timings.duration(id),
+                            app.collapsed_tool_calls.contains(id),
+                            available_width,
+                            &app.tool_display_mode,
+                            &app.tool_renderers,
+                            &mut app.tool_cards,
+                        )
+                    }
+                    _ => format_message_content(
+                        content,
+                        available_width,
+                        &app.tool_display_mode,
+                        &tool_names,
+                        &app.tool_renderers,
+                    ),
+                };
+                rendered_lines += content_lines.len();
                 for line in content_lines {
                     // Apply background highlighting to selected message content
                     let styled_line = if is_selected {
@@ -806,6 +1060,23 @@ fn render_chat_area(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
                     all_items.push(ListItem::new(styled_line));
                 }
             }
+
+            // A message holding only tool results has nothing left to show
+            if rendered_lines == 0 {
+                if !is_selected {
+                    continue;
+                }
+                all_items.push(ListItem::new(Line::from(vec![
+                    Span::styled(
+                        border_char,
+                        Style::default().fg(border_color).bg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        " ↳ tool results, shown with their calls",
+                        Style::default().fg(Color::Gray).bg(Color::DarkGray),
+                    ),
+                ])));
+            }
         }
 
         // Add model and token usage info for completions (only for non-collapsed messages)
@@ -908,7 +1179,9 @@ fn render_flexible_input_area(f: &mut Frame, area: ratatui::layout::Rect, app: &
     let input_block = Block::default().borders(Borders::ALL);
 
     let mut input_text = app.input.clone();
-    if app.waiting_for_response {
+    if app.compaction_pending {
+        input_text = format!("Compacting conversation{} (please wait)", app.thinking_dots);
+    } else if app.waiting_for_response {
         input_text = format!("Thinking{} (please wait)", app.thinking_dots);
     }
 
@@ -931,6 +1204,52 @@ fn render_flexible_input_area(f: &mut Frame, area: ratatui::layout::Rect, app: &
     }
 }
 
+/// Context usage ratio if it has crossed the warning threshold
+fn context_warning(app: &App, args: &CompatibleArgs) -> Option<f64> {
+    app.context_usage_ratio(args.context_window)
+        .filter(|ratio| *ratio >= CONTEXT_WARNING_THRESHOLD)
+}
+
+/// Render a warning banner when the conversation approaches the context window
+fn render_context_warning(
+    f: &mut Frame,
+    area: ratatui::layout::Rect,
+    app: &App,
+    args: &CompatibleArgs,
+) {
+    let Some(ratio) = context_warning(app, args) else {
+        return;
+    };
+
+    let (text, color) = if ratio >= 1.0 {
+        (
+            format!(
+                " Context window exceeded ({:.0}%) - the next request will likely fail. Start a new session.",
+                ratio * 100.0
+            ),
+            Color::Red,
+        )
+    } else {
+        (
+            format!(
+                " Context window {:.0}% full - consider starting a new session soon.",
+                ratio * 100.0
+            ),
+            Color::Yellow,
+        )
+    };
+
+    let banner = Paragraph::new(Line::from(Span::styled(
+        text,
+        Style::default()
+            .fg(Color::Black)
+            .bg(color)
+            .add_modifier(Modifier::BOLD),
+    )))
+    .style(Style::default().bg(color));
+    f.render_widget(banner, area);
+}
+
 /// Render the status bar
 fn render_status_bar(f: &mut Frame, area: ratatui::layout::Rect, app: &App, args: &CompatibleArgs) {
     let mode_text = match app.app_mode {
@@ -957,7 +1276,54 @@ fn render_status_bar(f: &mut Frame, area: ratatui::layout::Rect, app: &App, args
     let tool_mode = format!(" | Tools: {}", app.tool_display_mode.display_name());
     let split_screen_mode = format!(" | Panel: {}", if app.show_split_screen { "Split" } else { "Full" });
 
-    let status_line = Line::from(vec![
+    let last_turn = match &app.last_turn_usage {
+        Some(usage) => format!(
+            "{} in/{} out",
+            format_token_count(usage.input_tokens),
+            format_token_count(usage.output_tokens)
+        ),
+        None => "-".to_string(),
+    };
+    let mut usage_text = format!(
+        " | Last: {} | Session: {} in/{} out",
+        last_turn,
+        format_token_count(app.session_usage.input_tokens),
+        format_token_count(app.session_usage.output_tokens)
+    );
+    if app.session_usage.estimated_cost > 0.0 {
+        usage_text.push_str(&format!(" (~${:.4})", app.session_usage.estimated_cost));
+    }
+
+    let (context_text, context_color) = match (
+        app.context_usage_ratio(args.context_window),
+        args.context_window,
+    ) {
+        (Some(ratio), Some(window)) => {
+            let color = if ratio >= 1.0 {
+                Color::Red
+            } else if ratio >= CONTEXT_WARNING_THRESHOLD {
+                Color::LightRed
+            } else {
+                Color::Green
+            };
+            (
+                format!(
+                    " | Ctx: {:.0}% ({}/{})",
+                    ratio * 100.0,
+                    format_token_count(app.last_turn_usage.map_or(0, |u| u.input_tokens)),
+                    format_token_count(window as u64)
+                ),
+                color,
+            )
+        }
+        (None, Some(window)) => (
+            format!(" | Ctx: -/{}", format_token_count(window as u64)),
+            Color::Gray,
+        ),
+        _ => (" | Ctx: unknown".to_string(), Color::Gray),
+    };
+
+    let mut status_line = Line::from(vec![
         Span::styled(status_base, Style::default().fg(Color::White)),
         Span::styled(
             mode_text,
@@ -965,13 +1331,41 @@ fn render_status_bar(f: &mut Frame, area: ratatui::layout::Rect, app: &App, args
         ),
         Span::styled(tool_mode, Style::default().fg(Color::Cyan)),
         Span::styled(split_screen_mode, Style::default().fg(Color::Green)),
+        Span::styled(usage_text, Style::default().fg(Color::Yellow)),
+        Span::styled(context_text, Style::default().fg(context_color)),
     ]);
 
+    if let Some(retry) = &app.pending_retry {
+        status_line.spans.push(Span::styled(
+            format!(
+                " | {}: retrying in {}s (attempt {}/{})",
+                retry.code,
+                retry.seconds_remaining(),
+                retry.attempt,
+                retry.max_attempts
+            ),
+            Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
     let status_paragraph =
         Paragraph::new(vec![status_line]).style(Style::default().bg(Color::DarkGray));
     f.render_widget(status_paragraph, area);
 }
 
+/// Format a token count compactly (e.g. 950, 12.3k, 1.2M)
+fn format_token_count(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
 /// Render the enhanced help popup with navigation instructions
 fn render_help_popup(f: &mut Frame, area: ratatui::layout::Rect) {
     let popup_area = centered_rect(80, 90, area);
@@ -1024,6 +1418,8 @@ fn render_help_popup(f: &mut Frame, area: ratatui::layout::Rect) {
         Line::from("  Esc         - Return to View mode"),
         Line::from("  j / k / ↓ / ↑ - Navigate between messages"),
         Line::from("  c           - Toggle collapse/expand selected message"),
+        Line::from("  o           - Collapse/expand tool call cards in selected message"),
+        Line::from("  r           - Retry the request behind a selected error"),
         Line::from("  t           - Cycle tool display mode"),
         Line::from("  T           - Auto-collapse tool-heavy messages"),
         Line::from("  Selected message shows with ► indicator and highlighting"),
@@ -1055,6 +1451,18 @@ fn render_help_popup(f: &mut Frame, area: ratatui::layout::Rect) {
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from("  /help /clear /debug /status  (type in input area)"),
+        Line::from("  /compact   - Summarize and continue in a new session"),
+        Line::from("  /tools     - Show MCP servers and their tools"),
+        Line::from("  /timeline [tool] - List tool calls with per-tool statistics"),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            "Tool Approval:",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from("  y / n      - Approve or deny a tool call matching confirm_tools"),
+        Line::from("  A          - Always allow that tool for this session"),
         Line::from(""),
         Line::from(vec![Span::styled(
             "Tips:",
@@ -1080,6 +1488,327 @@ fn render_help_popup(f: &mut Frame, area: ratatui::layout::Rect) {
     f.render_widget(help_paragraph, popup_area);
 }
 
+/// Render the MCP servers and tools reported by the actor
+fn render_tools_popup(f: &mut Frame, area: ratatui::layout::Rect, app: &App) {
+    let popup_area = centered_rect(80, 90, area);
+    f.render_widget(Clear, popup_area);
+
+    let mut lines = Vec::new();
+    match &app.tool_inventory {
+        None => lines.push(Line::from("Tool inventory not loaded yet")),
+        Some(inventory) => {
+            if let Some(error) = &inventory.error {
+                lines.push(Line::from(vec![Span::styled(
+                    format!("Could not read actor settings: {}", error),
+                    Style::default().fg(Color::Red),
+                )]));
+                lines.push(Line::from(""));
+            }
+            if inventory.servers.is_empty() {
+                lines.push(Line::from("No MCP servers configured"));
+            }
+
+            for server in &inventory.servers {
+                let status_color = match server.state {
+                    ServerState::Loaded => Color::Green,
+                    ServerState::NoTools | ServerState::Unknown => Color::Yellow,
+                    ServerState::Skipped(_) | ServerState::Missing => Color::Red,
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        server.name.clone(),
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(" "),
+                    Span::styled(
+                        format!("[{}]", server.state.label()),
+                        Style::default().fg(status_color),
+                    ),
+                    Span::styled(
+                        format!(" {}", server.command),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ]));
+                if let ServerState::Skipped(reason) = &server.state {
+                    lines.push(Line::from(vec![Span::styled(
+                        format!("  {}", reason),
+                        Style::default().fg(Color::Red),
+                    )]));
+                }
+
+                for tool in &server.tools {
+                    let mut tool_line = vec![
+                        Span::styled("  • ", Style::default().fg(Color::Gray)),
+                        Span::styled(tool.name.clone(), Style::default().fg(Color::Yellow)),
+                    ];
+                    if let Some(reason) = app.tool_policy.check(&server.name, &tool.name) {
+                        tool_line.push(Span::styled(
+                            format!(" [blocked: {}]", reason),
+                            Style::default().fg(Color::Red),
+                        ));
+                    }
+                    lines.push(Line::from(tool_line));
+                    if let Some(description) = &tool.description {
+                        lines.push(Line::from(format!("      {}", description)));
+                    }
+                    if let Some(summary) = tool.input_schema.as_ref().and_then(schema_summary) {
+                        lines.push(Line::from(vec![Span::styled(
+                            format!("      ({})", summary),
+                            Style::default().fg(Color::Gray),
+                        )]));
+                    }
+                }
+                lines.push(Line::from(""));
+            }
+        }
+    }
+    lines.push(Line::from("j/k to scroll, q or Esc to close"));
+
+    let title = match &app.tool_inventory {
+        Some(inventory) => format!(
+            "Tools - {} from {} MCP servers",
+            inventory.tool_count(),
+            inventory.servers.len()
+        ),
+        None => "Tools".to_string(),
+    };
+    let tools_paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Black).fg(Color::White)),
+        )
+        .style(Style::default().bg(Color::Black).fg(Color::White))
+        .wrap(Wrap { trim: false })
+        .scroll((app.tools_scroll.min(u16::MAX as usize) as u16, 0));
+    f.render_widget(tools_paragraph, popup_area);
+}
+
+/// Render every tool call in the conversation as a timeline, below per-tool statistics
+fn render_timeline_popup(f: &mut Frame, area: ratatui::layout::Rect, app: &App) {
+    let popup_area = centered_rect(80, 90, area);
+    f.render_widget(Clear, popup_area);
+
+    let all_calls = app.tool_calls();
+    let counts = count_tool_calls(&all_calls);
+    let calls = app.timeline_calls();
+    let width = popup_area.width.saturating_sub(2) as usize;
+
+    let mut lines = Vec::new();
+    if all_calls.is_empty() {
+        lines.push(Line::from("No tool calls in this conversation yet"));
+    }
+    let tool_width = counts
+        .keys()
+        .map(|tool| tool.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (tool, count) in &counts {
+        let filtered = app.timeline_filter.as_deref() == Some(tool.as_str());
+        let mut spans = vec![
+            Span::styled(
+                format!("{:<tool_width$}", tool, tool_width = tool_width),
+                Style::default()
+                    .fg(if filtered { Color::Yellow } else { Color::Cyan })
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" {:>4} calls", count.calls)),
+        ];
+        if count.errors > 0 {
+            spans.push(Span::styled(
+                format!("  {} failed", count.errors),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        if count.blocked > 0 {
+            spans.push(Span::styled(
+                format!("  {} blocked", count.blocked),
+                Style::default().fg(Color::LightRed),
+            ));
+        }
+        spans.push(Span::styled(
+            format!("  {} output", format_size(count.output_bytes)),
+            Style::default().fg(Color::Gray),
+        ));
+        lines.push(Line::from(spans));
+    }
+    if !all_calls.is_empty() {
+        lines.push(Line::from(""));
+    }
+
+    // Fixed-width columns around the target, which gets the remaining width
+    let first_row = lines.len();
+    let name_width = calls
+        .iter()
+        .map(|call| call.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let target_width = width.saturating_sub(26 + name_width);
+    for (index, call) in calls.iter().enumerate() {
+        let (symbol, color) = match call.status() {
+            ToolCallStatus::Pending => ("⋯", Color::Yellow),
+            ToolCallStatus::Succeeded => ("✓", Color::Green),
+            ToolCallStatus::Failed => ("✗", Color::Red),
+            ToolCallStatus::Blocked => ("⊘", Color::LightRed),
+        };
+        let size = call
+            .result
+            .as_ref()
+            .map(|result| format_size(result.output_size() as u64))
+            .unwrap_or_else(|| "-".to_string());
+        let duration = app
+            .tool_timings
+            .duration(&call.id)
+            .map(format_duration)
+            .unwrap_or_default();
+        let target: String = call
+            .target()
+            .unwrap_or("")
+            .chars()
+            .take(target_width)
+            .collect();
+        let row_style = if index == app.timeline_selected {
+            Style::default().bg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:>3} ", index + 1), row_style.fg(Color::DarkGray)),
+            Span::styled(format!("{} ", symbol), row_style.fg(color)),
+            Span::styled(
+                format!("{:<name_width$}  ", call.name, name_width = name_width),
+                row_style.fg(Color::Cyan),
+            ),
+            Span::styled(
+                format!("{:<target_width$}", target, target_width = target_width),
+                row_style.fg(Color::White),
+            ),
+            Span::styled(format!(" {:>9}", size), row_style.fg(Color::Gray)),
+            Span::styled(format!(" {:>7}", duration), row_style.fg(Color::DarkGray)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        "j/k to select, Enter to go to the call, f to filter by tool, q or Esc to close",
+    ));
+
+    // Keep the selected row in view
+    let height = popup_area.height.saturating_sub(2) as usize;
+    let scroll = (first_row + app.timeline_selected + 1).saturating_sub(height);
+
+    let errors: u32 = counts.values().map(|count| count.errors).sum();
+    let blocked: u32 = counts.values().map(|count| count.blocked).sum();
+    let title = match &app.timeline_filter {
+        Some(tool) => format!(
+            "Tool calls - {} of {} ({})",
+            calls.len(),
+            all_calls.len(),
+            tool
+        ),
+        None if blocked > 0 => format!(
+            "Tool calls - {} ({} failed, {} blocked)",
+            all_calls.len(),
+            errors,
+            blocked
+        ),
+        None => format!("Tool calls - {} ({} failed)", all_calls.len(), errors),
+    };
+    let timeline_paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .title_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Black).fg(Color::White)),
+        )
+        .style(Style::default().bg(Color::Black).fg(Color::White))
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+    f.render_widget(timeline_paragraph, popup_area);
+}
+
+/// Render the modal asking to approve a tool call held by the actor
+fn render_approval_popup(
+    f: &mut Frame,
+    area: ratatui::layout::Rect,
+    approval: &PendingApproval,
+    waiting: usize,
+) {
+    let popup_area = centered_rect(70, 60, area);
+    f.render_widget(Clear, popup_area);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("The model wants to run ", Style::default().fg(Color::White)),
+            Span::styled(
+                approval.name.clone(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            "Input:",
+            Style::default().fg(Color::Yellow),
+        )]),
+    ];
+
+    let input = serde_json::to_string_pretty(&approval.input)
+        .unwrap_or_else(|_| approval.input.to_string());
+    lines.extend(input.lines().map(|line| Line::from(format!("  {}", line))));
+
+    lines.push(Line::from(""));
+    if waiting > 0 {
+        lines.push(Line::from(vec![Span::styled(
+            format!("{} more tool call(s) waiting for approval", waiting),
+            Style::default().fg(Color::Gray),
+        )]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled(
+            "y",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" approve   "),
+        Span::styled(
+            "n",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" deny   "),
+        Span::styled(
+            "A",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!(" always allow {} this session", approval.name)),
+    ]));
+
+    let approval_paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Approve Tool Call")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .style(Style::default().bg(Color::Black).fg(Color::White)),
+        )
+        .style(Style::default().bg(Color::Black).fg(Color::White))
+        .wrap(Wrap { trim: false });
+    f.render_widget(approval_paragraph, popup_area);
+}
+
 /// Render the help panel on the right side
 fn render_help_panel(f: &mut Frame, area: ratatui::layout::Rect, app: &App) {
     let help_block = Block::default()
@@ -1161,6 +1890,9 @@ fn get_mode_specific_help(app: &App) -> Vec<Line> {
                 Line::from("/clear - Clear screen"),
                 Line::from("/debug - Debug info"),
                 Line::from("/status - Connection status"),
+                Line::from("/compact - Summarize into new session"),
+                Line::from("/tools - MCP servers and tools"),
+                Line::from("/timeline - Tool call timeline"),
             ]);
         }
         AppMode::Chat => {
@@ -1173,6 +1905,8 @@ fn get_mode_specific_help(app: &App) -> Vec<Line> {
                 Line::from("Esc - Return to VIEW mode"),
                 Line::from("j/k/↓/↑ - Navigate messages"),
                 Line::from("c - Toggle collapse message"),
+                Line::from("o - Toggle tool cards"),
+                Line::from("r - Retry selected error"),
                 Line::from("t - Cycle tool display"),
                 Line::from("T - Auto-collapse tools"),
                 Line::from(""),